@@ -0,0 +1,542 @@
+use crate::archive::{
+    check_free_space, is_encrypted, join_volumes, joined_file_name, joined_path, list_entry_info,
+    open_tar, remove_joined, scan_entries, volume_parts, ArchiveKind, ExtractCancelled,
+    ExtractLimits, ExtractProgress, FlaggedEntry, LimitGuard, PasswordError, SpaceReservations,
+    UnsafeContentError, UnsafeFilePolicy,
+};
+use serde::{Deserialize, Serialize};
+use sevenz_rust::{
+    decompress_file_with_extract_fn as decompress_7z,
+    decompress_with_extract_fn_and_password as decompress_7z_with_password, Password,
+    SevenZArchiveEntry,
+};
+use std::error::Error;
+use std::fs::{self, create_dir_all, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use zip::read::ZipArchive;
+use zip::result::ZipError;
+
+// Joins an entry name onto the output directory, refusing absolute paths and anything that
+// would climb out of it with ".."
+pub fn safe_join(output_dir: &Path, entry_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let mut out_path = output_dir.to_path_buf();
+    for component in Path::new(&entry_name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => out_path.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("Refusing to extract unsafe path {:?}", entry_name).into()),
+        }
+    }
+    Ok(out_path)
+}
+
+pub type PasswordFn<'a> = Box<dyn FnMut(&Path) -> Option<String> + 'a>;
+pub type ConfirmUnsafeFn<'a> = Box<dyn FnMut(&Path, &[FlaggedEntry]) -> bool + 'a>;
+
+pub struct ExtractOptions<'a> {
+    // Asked for the password once an archive turns out to be encrypted. Returning None fails
+    // the extraction with PasswordError::Required.
+    pub password: PasswordFn<'a>,
+    pub unsafe_file_policy: UnsafeFilePolicy,
+    // Asked whether to extract anyway when the policy is Warn and unsafe files were found
+    pub confirm_unsafe: ConfirmUnsafeFn<'a>,
+    pub limits: ExtractLimits,
+    pub progress: Option<Arc<ExtractProgress>>,
+    // Shared by extractions running at the same time, so they don't count the same free space
+    pub reservations: Option<SpaceReservations>,
+}
+
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        Self {
+            password: Box::new(|_: &Path| None),
+            unsafe_file_policy: UnsafeFilePolicy::Warn,
+            confirm_unsafe: Box::new(|file_path: &Path, flagged: &[FlaggedEntry]| {
+                for entry in flagged {
+                    eprintln!(
+                        "Warning: {:?} contains {} {}",
+                        file_path, entry.reason, entry.name
+                    );
+                }
+                true
+            }),
+            limits: ExtractLimits::default(),
+            progress: None,
+            reservations: None,
+        }
+    }
+}
+
+pub fn extract_archive(file_path: &Path) -> Result<(), Box<dyn Error>> {
+    extract_archive_with(file_path, &mut ExtractOptions::default()).map(|_| ())
+}
+
+pub fn extract_archive_with_password(
+    file_path: &Path,
+    password: &mut dyn FnMut(&Path) -> Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let mut options = ExtractOptions {
+        password: Box::new(|archive_path: &Path| password(archive_path)),
+        ..Default::default()
+    };
+    extract_archive_with(file_path, &mut options).map(|_| ())
+}
+
+/// Extracts an archive next to itself, joining split volumes first. Returns the top level
+/// files and folders that were written.
+pub fn extract_archive_with(
+    file_path: &Path,
+    options: &mut ExtractOptions,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    // Ensure the provided file path exists and is a file
+    if !file_path.exists() || !file_path.is_file() {
+        return Err("Provided file_path does not exist or is not a valid file.".into());
+    }
+
+    // Get the parent directory and stem name for output
+    let parent_dir = file_path
+        .parent()
+        .ok_or("Unable to determine parent directory.")?;
+    let output_dir = parent_dir;
+
+    // Create the output directory if it doesn't exist
+    if !output_dir.exists() {
+        create_dir_all(&output_dir)?;
+    }
+
+    // Split archives are glued back together in the temp directory before extracting
+    let extracted = if let Some(parts) = volume_parts(file_path) {
+        let joined_name = joined_file_name(&parts[0], &parts[parts.len() - 1])
+            .ok_or("Unable to determine the name of the split archive.")?;
+        let joined_path = joined_path(&joined_name);
+        let joined_size = parts
+            .iter()
+            .map(|part| {
+                fs::metadata(part)
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0)
+            })
+            .sum();
+        println!("Joining {} volumes of {:?}", parts.len(), file_path);
+        // Whatever fails, a partly written join mustn't be left behind in the temp directory
+        let result = check_free_space(&joined_path, joined_size, 0)
+            .map_err(|err| err.into())
+            .and_then(|_| join_volumes(&parts, &joined_path))
+            .and_then(|_| extract_to(&joined_path, file_path, output_dir, options));
+        remove_joined(&joined_path);
+        result?
+    } else {
+        extract_to(file_path, file_path, output_dir, options)?
+    };
+
+    println!(
+        "Successfully extracted {:?} to {:?}",
+        file_path.file_name().unwrap(),
+        output_dir
+    );
+    record_extraction(file_path, &extracted);
+
+    Ok(extracted)
+}
+
+// Left next to an archive once it's extracted, so it isn't extracted again every time the mods
+// folder is scanned. Hidden like the staging folder, so it isn't taken for a mod.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ExtractionRecord {
+    // The archive as it was when extracted, a different download under the same name won't match
+    archive_size: u64,
+    archive_modified: u64,
+    // The top level files and folders it was extracted to
+    extracted: Vec<String>,
+}
+
+fn extraction_record_path(file_path: &Path) -> PathBuf {
+    file_path.with_file_name(format!(
+        ".{}.extracted",
+        file_path.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+// Size and modified time of the archive, what the record is compared against
+fn archive_stamp(file_path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), modified.as_secs()))
+}
+
+fn record_extraction(file_path: &Path, extracted: &[PathBuf]) {
+    let Some((archive_size, archive_modified)) = archive_stamp(file_path) else {
+        return;
+    };
+    let record = ExtractionRecord {
+        archive_size,
+        archive_modified,
+        extracted: extracted
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect(),
+    };
+    let path = extraction_record_path(file_path);
+    let written = serde_json::to_string_pretty(&record)
+        .map_err(|err| err.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|err| err.to_string()));
+    if let Err(err) = written {
+        eprintln!("Failed to write {:?}: {}", path, err);
+    }
+}
+
+/// Whether the archive was already extracted next to itself, hasn't changed since and
+/// everything it was extracted to is still there.
+pub fn is_extracted(file_path: &Path) -> bool {
+    let Ok(json) = fs::read_to_string(extraction_record_path(file_path)) else {
+        return false;
+    };
+    let Ok(record) = serde_json::from_str::<ExtractionRecord>(&json) else {
+        return false;
+    };
+    let Some(parent_dir) = file_path.parent() else {
+        return false;
+    };
+    archive_stamp(file_path) == Some((record.archive_size, record.archive_modified))
+        && !record.extracted.is_empty()
+        && record
+            .extracted
+            .iter()
+            .all(|name| parent_dir.join(name).exists())
+}
+
+// `archive_path` is what gets read, `file_path` is the archive the user knows about
+fn extract_to(
+    archive_path: &Path,
+    file_path: &Path,
+    output_dir: &Path,
+    options: &mut ExtractOptions,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let password = if is_encrypted(archive_path)? {
+        match (options.password)(file_path) {
+            Some(password) => Some(password),
+            None => return Err(PasswordError::Required(file_path.to_path_buf()).into()),
+        }
+    } else {
+        None
+    };
+
+    // Look through the entries before anything is written to disk
+    let entries = list_entry_info(archive_path, password.as_deref())?;
+    let archive_len = fs::metadata(archive_path)?.len();
+    options.limits.check_headers(&entries, archive_len)?;
+    let total_bytes = entries.iter().map(|entry| entry.size).sum();
+    // Given back when this returns, by which point everything has been written or removed again
+    let _reservation = match &options.reservations {
+        Some(reservations) => Some(reservations.reserve(
+            output_dir,
+            total_bytes,
+            options.progress.clone().unwrap_or_default(),
+        )?),
+        None => {
+            check_free_space(output_dir, total_bytes, 0)?;
+            None
+        }
+    };
+    if let Some(progress) = &options.progress {
+        progress.total_bytes.store(total_bytes, Ordering::Relaxed);
+        progress
+            .total_entries
+            .store(entries.len(), Ordering::Relaxed);
+    }
+    let entry_names: Vec<String> = entries.into_iter().map(|entry| entry.name).collect();
+    let flagged = scan_entries(&entry_names);
+    if !flagged.is_empty() {
+        let allowed = match options.unsafe_file_policy {
+            UnsafeFilePolicy::Block => false,
+            UnsafeFilePolicy::Warn => (options.confirm_unsafe)(file_path, &flagged),
+        };
+        if !allowed {
+            return Err(UnsafeContentError {
+                path: file_path.to_path_buf(),
+                flagged,
+                blocked: options.unsafe_file_policy == UnsafeFilePolicy::Block,
+            }
+            .into());
+        }
+    }
+
+    // Everything is unpacked into a staging folder first, so a failure part way through
+    // can be rolled back without leaving half an archive behind in the mods folder
+    let staging_dir = output_dir.join(format!(
+        ".{}.extracting",
+        file_path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)?;
+    }
+    create_dir_all(&staging_dir)?;
+    let mut guard = LimitGuard::new(&options.limits, archive_len, options.progress.clone());
+    let result = unpack(archive_path, file_path, &staging_dir, password, &mut guard)
+        .and_then(|_| merge_staged(&staging_dir, output_dir, file_path));
+    fs::remove_dir_all(&staging_dir).unwrap_or_else(|err| {
+        eprintln!(
+            "Failed to remove staging directory {:?}: {}",
+            staging_dir, err
+        )
+    });
+    result
+}
+
+// What merging the staging folder did to the mods folder, so it can be undone
+enum MergeStep {
+    Moved(PathBuf),
+    // Something that was in the way, kept in the backup folder until the merge is done
+    Replaced { path: PathBuf, backup: PathBuf },
+}
+
+// Moves everything from the staging folder into place. Files that already exist are kept
+// aside until the whole merge worked, so a failure part way through leaves the mods folder
+// as it was. Returns the top level paths that were moved.
+fn merge_staged(
+    staging_dir: &Path,
+    output_dir: &Path,
+    file_path: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let backup_dir = output_dir.join(format!(
+        ".{}.replaced",
+        file_path.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    if backup_dir.exists() {
+        fs::remove_dir_all(&backup_dir)?;
+    }
+    let mut steps = Vec::new();
+    let result = merge_into(staging_dir, output_dir, &backup_dir, &mut steps);
+    if result.is_err() {
+        undo_merge(steps);
+    }
+    if backup_dir.exists() {
+        fs::remove_dir_all(&backup_dir).unwrap_or_else(|err| {
+            eprintln!(
+                "Failed to remove backup directory {:?}: {}",
+                backup_dir, err
+            )
+        });
+    }
+    result
+}
+
+fn merge_into(
+    staging_dir: &Path,
+    output_dir: &Path,
+    backup_dir: &Path,
+    steps: &mut Vec<MergeStep>,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut merged = Vec::new();
+    for entry in fs::read_dir(staging_dir)? {
+        let entry = entry?;
+        let destination = output_dir.join(entry.file_name());
+        if entry.file_type()?.is_dir() && destination.is_dir() {
+            merge_into(&entry.path(), &destination, backup_dir, steps)?;
+        } else {
+            // Folders that aren't there yet are moved over whole
+            if destination.exists() {
+                create_dir_all(backup_dir)?;
+                let backup = backup_dir.join(steps.len().to_string());
+                fs::rename(&destination, &backup)?;
+                steps.push(MergeStep::Replaced {
+                    path: destination.clone(),
+                    backup,
+                });
+            }
+            fs::rename(entry.path(), &destination)?;
+            steps.push(MergeStep::Moved(destination.clone()));
+        }
+        merged.push(destination);
+    }
+    Ok(merged)
+}
+
+// Takes back what was moved in and puts replaced files back, newest first
+fn undo_merge(steps: Vec<MergeStep>) {
+    for step in steps.into_iter().rev() {
+        let (path, undone) = match &step {
+            MergeStep::Moved(path) if path.is_dir() => (path, fs::remove_dir_all(path)),
+            MergeStep::Moved(path) => (path, fs::remove_file(path)),
+            MergeStep::Replaced { path, backup } => (path, fs::rename(backup, path)),
+        };
+        if let Err(err) = undone {
+            eprintln!("Failed to undo extracting to {:?}: {}", path, err);
+        }
+    }
+}
+
+fn unpack(
+    archive_path: &Path,
+    file_path: &Path,
+    output_dir: &Path,
+    password: Option<String>,
+    guard: &mut LimitGuard,
+) -> Result<(), Box<dyn Error>> {
+    // Match based on file extension
+    match ArchiveKind::from_path(archive_path) {
+        Some(ArchiveKind::Zip) => {
+            let file = File::open(archive_path)?;
+            let mut archive = ZipArchive::new(file)?;
+
+            for i in 0..archive.len() {
+                let file = match &password {
+                    Some(password) => archive.by_index_decrypt(i, password.as_bytes()),
+                    None => archive.by_index(i),
+                };
+                let mut file = match file {
+                    Ok(file) => file,
+                    Err(ZipError::InvalidPassword) => {
+                        return Err(PasswordError::Incorrect(file_path.to_path_buf()).into())
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                let out_path = safe_join(output_dir, file.name())?;
+
+                if file.is_dir() {
+                    // Directory entry
+                    create_dir_all(&out_path)?;
+                } else if file.is_file() {
+                    // File entry
+                    let name = file.name().to_string();
+                    let size = file.size();
+                    guard.extract_file(&name, size, &mut file, &out_path)?;
+                } else {
+                    println!("Skipping link {} in {:?}", file.name(), file_path);
+                }
+            }
+        }
+        Some(ArchiveKind::SevenZ) => {
+            let extract_entry =
+                |entry: &SevenZArchiveEntry, reader: &mut dyn Read, _dest: &PathBuf| {
+                    let out_path = safe_join(output_dir, entry.name())
+                        .map_err(|err| sevenz_rust::Error::other(err.to_string()))?;
+                    if entry.is_directory() {
+                        create_dir_all(&out_path)?;
+                    } else {
+                        guard
+                            .extract_file(entry.name(), entry.size(), reader, &out_path)
+                            .map_err(|err| sevenz_rust::Error::other(err.to_string()))?;
+                    }
+                    Ok(true)
+                };
+            let result = match &password {
+                Some(password) => decompress_7z_with_password(
+                    File::open(archive_path)?,
+                    output_dir,
+                    Password::from(password.as_str()),
+                    extract_entry,
+                ),
+                None => decompress_7z(archive_path, output_dir, extract_entry),
+            };
+            match result {
+                Ok(()) => {}
+                // The cancellation only reaches us as a plain 7z error
+                Err(_) if guard.is_cancelled() => return Err(ExtractCancelled.into()),
+                Err(sevenz_rust::Error::PasswordRequired) => {
+                    return Err(PasswordError::Required(file_path.to_path_buf()).into())
+                }
+                Err(sevenz_rust::Error::MaybeBadPassword(_))
+                | Err(sevenz_rust::Error::ChecksumVerificationFailed)
+                    if password.is_some() =>
+                {
+                    return Err(PasswordError::Incorrect(file_path.to_path_buf()).into())
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Some(kind) => {
+            let mut archive = open_tar(archive_path, kind)?;
+
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let entry_name = entry.path()?.to_string_lossy().to_string();
+                let out_path = safe_join(output_dir, &entry_name)?;
+                let entry_type = entry.header().entry_type();
+
+                if entry_type.is_dir() {
+                    create_dir_all(&out_path)?;
+                } else if entry_type.is_file() {
+                    let size = entry.size();
+                    guard.extract_file(&entry_name, size, &mut entry, &out_path)?;
+                } else {
+                    // Links and device files have no place in a mod
+                    println!("Skipping {} in {:?}", entry_name, file_path);
+                }
+            }
+        }
+        None => {
+            return Err(format!(
+                "Unsupported file format. Supported archives are {}.",
+                ArchiveKind::SUPPORTED
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+
+    #[test]
+    fn safe_join_stays_inside_the_output_dir() {
+        let output_dir = Path::new("mods");
+        assert_eq!(
+            safe_join(output_dir, "Suit/./Suit_P.pak").unwrap(),
+            Path::new("mods/Suit/Suit_P.pak")
+        );
+        assert_eq!(
+            safe_join(output_dir, "Suit\\images\\cover.png").unwrap(),
+            Path::new("mods/Suit/images/cover.png")
+        );
+        assert!(safe_join(output_dir, "../Suit_P.pak").is_err());
+        assert!(safe_join(output_dir, "Suit/../../Suit_P.pak").is_err());
+        assert!(safe_join(output_dir, "Suit\\..\\..\\Suit_P.pak").is_err());
+        assert!(safe_join(output_dir, "/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn undoing_a_merge_restores_the_output_dir() {
+        let dir = test_dir("undo_merge");
+        let (staging_dir, output_dir) = (dir.join("staging"), dir.join("mods"));
+        fs::create_dir_all(staging_dir.join("Suit/images")).unwrap();
+        fs::create_dir_all(output_dir.join("Suit")).unwrap();
+        fs::write(staging_dir.join("Suit/Suit_P.pak"), "new").unwrap();
+        fs::write(staging_dir.join("Suit/images/cover.png"), "new").unwrap();
+        fs::write(staging_dir.join("Other_P.pak"), "new").unwrap();
+        fs::write(output_dir.join("Suit/Suit_P.pak"), "old").unwrap();
+
+        let backup_dir = dir.join("backup");
+        let mut steps = Vec::new();
+        let mut merged = merge_into(&staging_dir, &output_dir, &backup_dir, &mut steps).unwrap();
+        merged.sort();
+        assert_eq!(
+            merged,
+            [output_dir.join("Other_P.pak"), output_dir.join("Suit")]
+        );
+        assert_eq!(
+            fs::read_to_string(output_dir.join("Suit/Suit_P.pak")).unwrap(),
+            "new"
+        );
+        assert!(output_dir.join("Suit/images/cover.png").exists());
+
+        undo_merge(steps);
+        assert_eq!(
+            fs::read_to_string(output_dir.join("Suit/Suit_P.pak")).unwrap(),
+            "old"
+        );
+        assert!(!output_dir.join("Suit/images").exists());
+        assert!(!output_dir.join("Other_P.pak").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,237 @@
+use crate::archive::{EntryInfo, ExtractCancelled, ExtractProgress};
+use std::error::Error;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+// Caps on what a single archive may unpack to, so a zip bomb fails instead of filling the disk
+#[derive(Debug, Clone)]
+pub struct ExtractLimits {
+    pub max_total_size: u64,
+    pub max_entries: usize,
+    // Uncompressed bytes allowed per compressed byte
+    pub max_ratio: u64,
+    pub max_depth: usize,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: 16 * 1024 * 1024 * 1024,
+            max_entries: 10_000,
+            max_ratio: 100,
+            max_depth: 16,
+        }
+    }
+}
+
+fn entry_depth(entry_name: &str) -> usize {
+    entry_name
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .count()
+}
+
+impl ExtractLimits {
+    /// Checks what the archive headers declare before anything is extracted.
+    pub fn check_headers(
+        &self,
+        entries: &[EntryInfo],
+        archive_len: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        if entries.len() > self.max_entries {
+            return Err(format!(
+                "Archive has {} entries, more than the limit of {}",
+                entries.len(),
+                self.max_entries
+            )
+            .into());
+        }
+        let mut total_size: u64 = 0;
+        for entry in entries {
+            self.check_depth(&entry.name)?;
+            if entry.compressed_size > 0 && entry.size / entry.compressed_size > self.max_ratio {
+                return Err(format!(
+                    "{} is compressed more than {}:1, refusing to extract",
+                    entry.name, self.max_ratio
+                )
+                .into());
+            }
+            total_size = total_size.saturating_add(entry.size);
+        }
+        self.check_total(total_size, archive_len)
+    }
+    fn check_depth(&self, entry_name: &str) -> Result<(), Box<dyn Error>> {
+        if entry_depth(entry_name) > self.max_depth {
+            return Err(format!(
+                "{} is nested more than {} folders deep",
+                entry_name, self.max_depth
+            )
+            .into());
+        }
+        Ok(())
+    }
+    fn check_total(&self, total_size: u64, archive_len: u64) -> Result<(), Box<dyn Error>> {
+        if total_size > self.max_total_size {
+            return Err(format!(
+                "Archive unpacks to more than the limit of {} bytes",
+                self.max_total_size
+            )
+            .into());
+        }
+        if total_size > archive_len.saturating_mul(self.max_ratio) {
+            return Err(format!(
+                "Archive is compressed more than {}:1, refusing to extract",
+                self.max_ratio
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+// Enforces the limits again while writing, since headers can lie about sizes. Also reports
+// progress and notices cancellation between chunks.
+pub(crate) struct LimitGuard<'a> {
+    limits: &'a ExtractLimits,
+    archive_len: u64,
+    written: u64,
+    entries: usize,
+    progress: Option<Arc<ExtractProgress>>,
+}
+
+impl<'a> LimitGuard<'a> {
+    pub(crate) fn new(
+        limits: &'a ExtractLimits,
+        archive_len: u64,
+        progress: Option<Arc<ExtractProgress>>,
+    ) -> Self {
+        Self {
+            limits,
+            archive_len,
+            written: 0,
+            entries: 0,
+            progress,
+        }
+    }
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.progress
+            .as_ref()
+            .is_some_and(|progress| progress.is_cancelled())
+    }
+    pub(crate) fn extract_file(
+        &mut self,
+        entry_name: &str,
+        declared_size: u64,
+        reader: &mut dyn Read,
+        out_path: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(format!(
+                "Archive has more than the limit of {} entries",
+                self.limits.max_entries
+            )
+            .into());
+        }
+        self.limits.check_depth(entry_name)?;
+
+        if let Some(parent) = out_path.parent() {
+            create_dir_all(parent)?;
+        }
+        let mut out_file = File::create(out_path)?;
+        let mut buffer = vec![0; 64 * 1024];
+        let mut entry_written: u64 = 0;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            entry_written += read as u64;
+            self.written += read as u64;
+            if entry_written > declared_size {
+                return Err(format!("{} is larger than its header declares", entry_name).into());
+            }
+            self.limits.check_total(self.written, self.archive_len)?;
+            out_file.write_all(&buffer[..read])?;
+            if let Some(progress) = &self.progress {
+                progress
+                    .bytes_written
+                    .fetch_add(read as u64, Ordering::Relaxed);
+                if progress.is_cancelled() {
+                    return Err(ExtractCancelled.into());
+                }
+            }
+        }
+        if let Some(progress) = &self.progress {
+            progress.entries_written.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+
+    fn entry(name: &str, size: u64, compressed_size: u64) -> EntryInfo {
+        EntryInfo {
+            name: name.to_string(),
+            size,
+            compressed_size,
+        }
+    }
+
+    fn limits() -> ExtractLimits {
+        ExtractLimits {
+            max_total_size: 1000,
+            max_entries: 2,
+            max_ratio: 10,
+            max_depth: 3,
+        }
+    }
+
+    #[test]
+    fn headers_within_limits_pass() {
+        let entries = [
+            entry("Suit/Suit_P.pak", 500, 100),
+            entry("./Suit/a/b", 0, 0),
+        ];
+        assert!(limits().check_headers(&entries, 100).is_ok());
+    }
+
+    #[test]
+    fn headers_over_limits_fail() {
+        let limits = limits();
+        let too_many = [entry("a", 1, 1), entry("b", 1, 1), entry("c", 1, 1)];
+        assert!(limits.check_headers(&too_many, 100).is_err());
+        let too_deep = [entry("a/b/c/d.pak", 1, 1)];
+        assert!(limits.check_headers(&too_deep, 100).is_err());
+        let entry_ratio = [entry("Suit_P.pak", 110, 10)];
+        assert!(limits.check_headers(&entry_ratio, 100).is_err());
+        let too_big = [entry("Suit_P.pak", 1001, 0)];
+        assert!(limits.check_headers(&too_big, 1000).is_err());
+        // Solid archives don't say how well each entry compressed, only the whole is checked
+        let archive_ratio = [entry("Suit_P.pak", 200, 0)];
+        assert!(limits.check_headers(&archive_ratio, 19).is_err());
+        assert!(limits.check_headers(&archive_ratio, 20).is_ok());
+    }
+
+    #[test]
+    fn entries_larger_than_declared_fail_while_writing() {
+        let dir = test_dir("limit_guard");
+        let limits = limits();
+        let mut guard = LimitGuard::new(&limits, 100, None);
+        let out_path = dir.join("Suit_P.pak");
+        assert!(guard
+            .extract_file("Suit_P.pak", 5, &mut &b"12345"[..], &out_path)
+            .is_ok());
+        assert!(guard
+            .extract_file("Other_P.pak", 5, &mut &b"123456"[..], &out_path)
+            .is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
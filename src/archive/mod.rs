@@ -0,0 +1,19 @@
+mod extract;
+mod limits;
+mod password;
+mod pool;
+mod progress;
+mod reader;
+mod scan;
+mod space;
+mod volumes;
+
+pub use extract::*;
+pub use limits::*;
+pub use password::*;
+pub use pool::*;
+pub use progress::*;
+pub use reader::*;
+pub use scan::*;
+pub use space::*;
+pub use volumes::*;
@@ -0,0 +1,62 @@
+use crate::archive::ArchiveKind;
+use sevenz_rust::{Archive, SevenZMethod};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use zip::read::ZipArchive;
+
+// Returned by extraction when an encrypted archive has no password or the wrong one, so callers
+// can downcast the error and ask the user for it
+#[derive(Debug)]
+pub enum PasswordError {
+    Required(PathBuf),
+    Incorrect(PathBuf),
+}
+
+impl PasswordError {
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            PasswordError::Required(path) => path,
+            PasswordError::Incorrect(path) => path,
+        }
+    }
+}
+
+impl Display for PasswordError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordError::Required(path) => write!(f, "{:?} is password protected", path),
+            PasswordError::Incorrect(path) => write!(f, "Incorrect password for {:?}", path),
+        }
+    }
+}
+
+impl Error for PasswordError {}
+
+/// Checks whether any entry of the archive needs a password to be read.
+pub fn is_encrypted(archive_path: &Path) -> Result<bool, Box<dyn Error>> {
+    match ArchiveKind::from_path(archive_path) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+            for i in 0..archive.len() {
+                if archive.by_index_raw(i)?.encrypted() {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Some(ArchiveKind::SevenZ) => match Archive::open(archive_path) {
+            Ok(archive) => Ok(archive.folders.iter().any(|folder| {
+                folder
+                    .coders
+                    .iter()
+                    .any(|coder| coder.decompression_method_id() == SevenZMethod::ID_AES256SHA256)
+            })),
+            // The file list itself is encrypted
+            Err(sevenz_rust::Error::PasswordRequired) => Ok(true),
+            Err(err) => Err(err.into()),
+        },
+        _ => Ok(false),
+    }
+}
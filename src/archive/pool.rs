@@ -0,0 +1,180 @@
+use crate::archive::{
+    extract_archive_with, ExtractCancelled, ExtractLimits, ExtractOptions, ExtractProgress,
+    FlaggedEntry, InsufficientSpaceError, PasswordError, SpaceReservations, UnsafeContentError,
+    UnsafeFilePolicy,
+};
+use std::any::Any;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Everything a worker needs to extract an archive without asking anyone. Prompts happen on
+// the caller's side by resubmitting the job once the password or approval is known.
+#[derive(Debug)]
+pub struct ExtractJob {
+    pub path: PathBuf,
+    pub password: Option<String>,
+    pub unsafe_file_policy: UnsafeFilePolicy,
+    pub allow_unsafe: bool,
+    pub limits: ExtractLimits,
+    progress: Arc<ExtractProgress>,
+    reservations: Option<SpaceReservations>,
+}
+
+impl ExtractJob {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            password: None,
+            unsafe_file_policy: UnsafeFilePolicy::default(),
+            allow_unsafe: false,
+            limits: ExtractLimits::default(),
+            progress: Arc::default(),
+            reservations: None,
+        }
+    }
+    fn run(self) -> Result<Vec<PathBuf>, ExtractFailure> {
+        if self.progress.is_cancelled() {
+            return Err(ExtractFailure::Cancelled);
+        }
+        let password = self.password;
+        let allow_unsafe = self.allow_unsafe;
+        let mut options = ExtractOptions {
+            password: Box::new(move |_: &Path| password.clone()),
+            unsafe_file_policy: self.unsafe_file_policy,
+            confirm_unsafe: Box::new(move |_: &Path, _: &[FlaggedEntry]| allow_unsafe),
+            limits: self.limits,
+            progress: Some(self.progress),
+            reservations: self.reservations,
+        };
+        extract_archive_with(&self.path, &mut options).map_err(ExtractFailure::from)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExtractFailure {
+    Password(PasswordError),
+    UnsafeContent(UnsafeContentError),
+    NoSpace(InsufficientSpaceError),
+    Cancelled,
+    Failed(String),
+}
+
+impl ExtractFailure {
+    fn panicked(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(message) => message.to_string(),
+                Err(_) => "unknown error".to_string(),
+            },
+        };
+        ExtractFailure::Failed(format!("Extraction stopped unexpectedly: {}", message))
+    }
+}
+
+impl From<Box<dyn Error>> for ExtractFailure {
+    fn from(err: Box<dyn Error>) -> Self {
+        let err = match err.downcast::<PasswordError>() {
+            Ok(password_error) => return ExtractFailure::Password(*password_error),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<UnsafeContentError>() {
+            Ok(unsafe_content) => return ExtractFailure::UnsafeContent(*unsafe_content),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<InsufficientSpaceError>() {
+            Ok(no_space) => return ExtractFailure::NoSpace(*no_space),
+            Err(err) => err,
+        };
+        if err.is::<ExtractCancelled>() {
+            return ExtractFailure::Cancelled;
+        }
+        ExtractFailure::Failed(err.to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct ExtractOutcome {
+    pub path: PathBuf,
+    // The top level files and folders the archive was extracted to
+    pub result: Result<Vec<PathBuf>, ExtractFailure>,
+}
+
+// Extracts archives on background threads. Results are collected with poll(), which is meant
+// to be called once per frame.
+#[derive(Debug, Default)]
+pub struct ExtractionPool {
+    jobs: Option<Sender<ExtractJob>>,
+    outcomes: Option<Receiver<ExtractOutcome>>,
+    active: Vec<(PathBuf, Arc<ExtractProgress>)>,
+    reservations: SpaceReservations,
+}
+
+impl ExtractionPool {
+    fn start(&mut self) -> &Sender<ExtractJob> {
+        if self.jobs.is_none() {
+            let (job_sender, job_receiver) = channel::<ExtractJob>();
+            let (outcome_sender, outcome_receiver) = channel();
+            let job_receiver = Arc::new(Mutex::new(job_receiver));
+            let workers = thread::available_parallelism()
+                .map(|count| count.get())
+                .unwrap_or(2)
+                .min(4);
+            for _ in 0..workers {
+                let job_receiver = Arc::clone(&job_receiver);
+                let outcome_sender: Sender<ExtractOutcome> = outcome_sender.clone();
+                thread::spawn(move || loop {
+                    let job = match job_receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let path = job.path.clone();
+                    // A panicking job still has to report back, or it would stay active forever
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| job.run()))
+                        .unwrap_or_else(|payload| Err(ExtractFailure::panicked(payload)));
+                    if outcome_sender
+                        .send(ExtractOutcome { path, result })
+                        .is_err()
+                    {
+                        break;
+                    }
+                });
+            }
+            self.jobs = Some(job_sender);
+            self.outcomes = Some(outcome_receiver);
+        }
+        self.jobs.as_ref().unwrap()
+    }
+    /// Queues an archive, unless it is already being extracted.
+    pub fn submit(&mut self, mut job: ExtractJob) -> bool {
+        if self.is_active(&job.path) {
+            return false;
+        }
+        let progress = Arc::new(ExtractProgress::default());
+        job.progress = Arc::clone(&progress);
+        job.reservations = Some(self.reservations.clone());
+        self.active.push((job.path.clone(), progress));
+        self.start().send(job).is_ok()
+    }
+    pub fn is_active(&self, path: &Path) -> bool {
+        self.active
+            .iter()
+            .any(|(active_path, _)| active_path == path)
+    }
+    pub fn active(&self) -> &[(PathBuf, Arc<ExtractProgress>)] {
+        &self.active
+    }
+    pub fn poll(&mut self) -> Vec<ExtractOutcome> {
+        let Some(outcomes) = &self.outcomes else {
+            return Vec::new();
+        };
+        let finished: Vec<ExtractOutcome> = outcomes.try_iter().collect();
+        self.active
+            .retain(|(path, _)| !finished.iter().any(|outcome| outcome.path == *path));
+        finished
+    }
+}
@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+// Shared between an extraction and whoever is watching it. Totals are filled in from the
+// archive headers once they have been read.
+#[derive(Debug, Default)]
+pub struct ExtractProgress {
+    pub bytes_written: AtomicU64,
+    pub total_bytes: AtomicU64,
+    pub entries_written: AtomicUsize,
+    pub total_entries: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl ExtractProgress {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+    pub fn fraction(&self) -> f32 {
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        (self.bytes_written.load(Ordering::Relaxed) as f64 / total as f64).min(1.0) as f32
+    }
+}
+
+#[derive(Debug)]
+pub struct ExtractCancelled;
+
+impl Display for ExtractCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Extraction was cancelled")
+    }
+}
+
+impl Error for ExtractCancelled {}
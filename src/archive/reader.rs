@@ -0,0 +1,284 @@
+use crate::archive::PasswordError;
+use flate2::read::GzDecoder;
+use sevenz_rust::{Archive, BlockDecoder, Password, SevenZArchiveEntry, SevenZMethod};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use xz2::read::XzDecoder;
+use zip::read::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    SevenZ,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl ArchiveKind {
+    pub const SUPPORTED: &'static str = ".zip, .7z, .tar, .tar.gz, .tar.xz and .tar.zst";
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        // Compressed tarballs have a double extension, so match on the whole file name
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        if file_name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if file_name.ends_with(".7z") {
+            Some(ArchiveKind::SevenZ)
+        } else if file_name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if file_name.ends_with(".tar.xz") || file_name.ends_with(".txz") {
+            Some(ArchiveKind::TarXz)
+        } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tzst") {
+            Some(ArchiveKind::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveKind::from_path(path).is_some() || crate::archive::is_volume(path)
+}
+
+fn archive_kind(path: &Path) -> Result<ArchiveKind, Box<dyn Error>> {
+    ArchiveKind::from_path(path).ok_or_else(|| {
+        format!(
+            "Unsupported file format. Supported archives are {}.",
+            ArchiveKind::SUPPORTED
+        )
+        .into()
+    })
+}
+
+/// Opens a tarball, wrapping it in the decompressor matching its extension.
+pub fn open_tar(
+    archive_path: &Path,
+    kind: ArchiveKind,
+) -> Result<tar::Archive<Box<dyn Read>>, Box<dyn Error>> {
+    let file = BufReader::new(File::open(archive_path)?);
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::Tar => Box::new(file),
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        ArchiveKind::TarXz => Box::new(XzDecoder::new(file)),
+        ArchiveKind::TarZst => Box::new(zstd::Decoder::with_buffer(file)?),
+        _ => return Err(format!("{:?} is not a tar archive", archive_path).into()),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+// Archives are not consistent about separators, so every entry name is compared using '/'
+fn normalize_entry_name(name: &str) -> String {
+    name.replace('\\', "/")
+}
+
+/// Lists the names of every file entry in the archive, skipping directories.
+pub fn list_entries(archive_path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    list_entries_with_password(archive_path, None)
+}
+
+// Only 7z can encrypt its file list, zip and tar names are always readable
+pub fn list_entries_with_password(
+    archive_path: &Path,
+    password: Option<&str>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(list_entry_info(archive_path, password)?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct EntryInfo {
+    pub name: String,
+    // Uncompressed size as declared by the archive headers
+    pub size: u64,
+    // Zero when the format doesn't record it per entry (solid 7z blocks, tarballs)
+    pub compressed_size: u64,
+}
+
+/// Lists every file entry along with the sizes its headers declare.
+pub fn list_entry_info(
+    archive_path: &Path,
+    password: Option<&str>,
+) -> Result<Vec<EntryInfo>, Box<dyn Error>> {
+    let mut entries = Vec::new();
+    match archive_kind(archive_path)? {
+        ArchiveKind::Zip => {
+            let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index_raw(i)?;
+                if file.is_file() {
+                    entries.push(EntryInfo {
+                        name: normalize_entry_name(file.name()),
+                        size: file.size(),
+                        compressed_size: file.compressed_size(),
+                    });
+                }
+            }
+        }
+        ArchiveKind::SevenZ => {
+            let password = password.map(Password::from).unwrap_or(Password::empty());
+            let archive = Archive::open_with_password(archive_path, &password)?;
+            for entry in &archive.files {
+                if !entry.is_directory() {
+                    entries.push(EntryInfo {
+                        name: normalize_entry_name(entry.name()),
+                        size: entry.size(),
+                        compressed_size: entry.compressed_size,
+                    });
+                }
+            }
+        }
+        kind => {
+            let mut archive = open_tar(archive_path, kind)?;
+            for entry in archive.entries()? {
+                let entry = entry?;
+                if entry.header().entry_type().is_file() {
+                    entries.push(EntryInfo {
+                        name: normalize_entry_name(&entry.path()?.to_string_lossy()),
+                        size: entry.size(),
+                        compressed_size: 0,
+                    });
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Opens a single entry of the archive and hands its contents to `read`.
+///
+/// Only the entry itself is decompressed for zip files. Solid 7z blocks and tarballs have to be
+/// decoded from their start, so the entries in front of it are skipped over.
+///
+/// Encrypted entries fail with `PasswordError::Required`, those archives are extracted with
+/// their password instead of being read in place.
+pub fn with_entry<T>(
+    archive_path: &Path,
+    entry_name: &str,
+    read: impl FnOnce(&mut dyn Read) -> io::Result<T>,
+) -> Result<T, Box<dyn Error>> {
+    match archive_kind(archive_path)? {
+        ArchiveKind::Zip => {
+            let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+            let index = (0..archive.len())
+                .find(|&i| {
+                    archive
+                        .name_for_index(i)
+                        .is_some_and(|name| normalize_entry_name(name) == entry_name)
+                })
+                .ok_or_else(|| format!("{} not found in {:?}", entry_name, archive_path))?;
+            if archive.by_index_raw(index)?.encrypted() {
+                return Err(PasswordError::Required(archive_path.to_path_buf()).into());
+            }
+            let mut file = archive.by_index(index)?;
+            Ok(read(&mut file)?)
+        }
+        ArchiveKind::SevenZ => {
+            let mut source = File::open(archive_path)?;
+            let len = source.metadata()?.len();
+            let archive = match Archive::read(&mut source, len, Password::empty().as_ref()) {
+                Ok(archive) => archive,
+                // The file list itself is encrypted
+                Err(sevenz_rust::Error::PasswordRequired) => {
+                    return Err(PasswordError::Required(archive_path.to_path_buf()).into())
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let file_index = archive
+                .files
+                .iter()
+                .position(|entry| normalize_entry_name(entry.name()) == entry_name)
+                .ok_or_else(|| format!("{} not found in {:?}", entry_name, archive_path))?;
+            let Some(folder_index) = archive.stream_map.file_folder_index[file_index] else {
+                // Empty files have no stream to decode
+                return Ok(read(&mut io::empty())?);
+            };
+            let encrypted = archive.folders[folder_index]
+                .coders
+                .iter()
+                .any(|coder| coder.decompression_method_id() == SevenZMethod::ID_AES256SHA256);
+            if encrypted {
+                return Err(PasswordError::Required(archive_path.to_path_buf()).into());
+            }
+
+            let mut read = Some(read);
+            let mut result = None;
+            BlockDecoder::new(folder_index, &archive, &[], &mut source).for_each_entries(
+                &mut |entry: &SevenZArchiveEntry, reader: &mut dyn Read| {
+                    if normalize_entry_name(entry.name()) == entry_name {
+                        if let Some(read) = read.take() {
+                            result = Some(read(reader)?);
+                        }
+                        return Ok(false);
+                    }
+                    io::copy(reader, &mut io::sink())?;
+                    Ok(true)
+                },
+            )?;
+            result.ok_or_else(|| {
+                format!("Failed to read {} from {:?}", entry_name, archive_path).into()
+            })
+        }
+        kind => {
+            let mut archive = open_tar(archive_path, kind)?;
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if entry.header().entry_type().is_file()
+                    && normalize_entry_name(&entry.path()?.to_string_lossy()) == entry_name
+                {
+                    return Ok(read(&mut entry)?);
+                }
+            }
+            Err(format!("{} not found in {:?}", entry_name, archive_path).into())
+        }
+    }
+}
+
+pub fn read_entry(archive_path: &Path, entry_name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    with_entry(archive_path, entry_name, |reader| {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    })
+}
+
+/// Streams a single entry of the archive into `destination` without extracting the rest.
+pub fn copy_entry(
+    archive_path: &Path,
+    entry_name: &str,
+    destination: &Path,
+) -> Result<u64, Box<dyn Error>> {
+    with_entry(archive_path, entry_name, |reader| {
+        let mut out_file = File::create(destination)?;
+        io::copy(reader, &mut out_file)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_kind_from_file_name() {
+        let kind = |name: &str| ArchiveKind::from_path(Path::new(name));
+        assert_eq!(kind("Suit.ZIP"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("mods/Suit.7z"), Some(ArchiveKind::SevenZ));
+        assert_eq!(kind("Suit.tar"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("Suit.tar.gz"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("Suit.tgz"), Some(ArchiveKind::TarGz));
+        assert_eq!(kind("Suit.tar.xz"), Some(ArchiveKind::TarXz));
+        assert_eq!(kind("Suit.tzst"), Some(ArchiveKind::TarZst));
+        assert_eq!(kind("Suit.rar"), None);
+        assert_eq!(kind("Suit_P.pak"), None);
+        // Volumes aren't archives by themselves until they're joined
+        assert_eq!(kind("Suit.7z.001"), None);
+        assert!(is_archive(Path::new("Suit.7z.001")));
+    }
+}
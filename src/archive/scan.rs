@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+// What to do with an archive that contains files a cosmetic mod has no reason to ship
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnsafeFilePolicy {
+    #[default]
+    Warn,
+    Block,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlaggedEntry {
+    pub name: String,
+    pub reason: &'static str,
+}
+
+const EXECUTABLES: [&str; 6] = ["exe", "com", "scr", "msi", "pif", "jar"];
+const SCRIPTS: [&str; 13] = [
+    "bat", "cmd", "ps1", "psm1", "vbs", "vbe", "js", "jse", "wsf", "wsh", "hta", "sh", "py",
+];
+const LIBRARIES: [&str; 6] = ["dll", "sys", "ocx", "cpl", "so", "dylib"];
+const SHORTCUTS: [&str; 3] = ["lnk", "url", "scf"];
+
+fn classify(entry_name: &str) -> Option<&'static str> {
+    let file_name = entry_name.rsplit(['/', '\\']).next().unwrap_or(entry_name);
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    if EXECUTABLES.contains(&extension.as_str()) {
+        Some("executable")
+    } else if SCRIPTS.contains(&extension.as_str()) {
+        Some("script")
+    } else if LIBRARIES.contains(&extension.as_str()) {
+        Some("library")
+    } else if SHORTCUTS.contains(&extension.as_str()) {
+        Some("shortcut")
+    } else {
+        None
+    }
+}
+
+/// Picks out the entries that could run code or point somewhere else on the system.
+pub fn scan_entries(entry_names: &[String]) -> Vec<FlaggedEntry> {
+    entry_names
+        .iter()
+        .filter_map(|name| {
+            classify(name).map(|reason| FlaggedEntry {
+                name: name.clone(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+// Returned by extraction when an archive was refused because of its flagged entries
+#[derive(Debug)]
+pub struct UnsafeContentError {
+    pub path: PathBuf,
+    pub flagged: Vec<FlaggedEntry>,
+    // True when the Block policy refused it, false when the user declined a warning
+    pub blocked: bool,
+}
+
+impl Display for UnsafeContentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} contains {} unsafe file(s): ",
+            self.path,
+            self.flagged.len()
+        )?;
+        let names: Vec<&str> = self
+            .flagged
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+impl Error for UnsafeContentError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_files_that_can_run_code() {
+        let names: Vec<String> = [
+            "Suit/Suit_P.pak",
+            "Suit/readme.txt",
+            "Suit/Setup.EXE",
+            "Suit\\tools\\install.bat",
+            "Suit/x64/hook.dll",
+            "Suit/Nexus.url",
+            "Suit/LICENSE",
+        ]
+        .map(String::from)
+        .to_vec();
+        let flagged: Vec<(String, &str)> = scan_entries(&names)
+            .into_iter()
+            .map(|entry| (entry.name, entry.reason))
+            .collect();
+        assert_eq!(
+            flagged,
+            [
+                ("Suit/Setup.EXE".to_string(), "executable"),
+                ("Suit\\tools\\install.bat".to_string(), "script"),
+                ("Suit/x64/hook.dll".to_string(), "library"),
+                ("Suit/Nexus.url".to_string(), "shortcut"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use crate::archive::ExtractProgress;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+// Returned when a drive doesn't have room for what's about to be written to it
+#[derive(Debug)]
+pub struct InsufficientSpaceError {
+    pub path: PathBuf,
+    pub required: u64,
+    pub available: u64,
+}
+
+impl Display for InsufficientSpaceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not enough space for {:?}: {} needed but only {} free, free up {} and try again",
+            self.path,
+            format_size(self.required),
+            format_size(self.available),
+            format_size(self.required - self.available)
+        )
+    }
+}
+
+impl Error for InsufficientSpaceError {}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} bytes", bytes);
+    }
+    let mut size = bytes as f64;
+    let mut unit = "";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", size, unit)
+}
+
+/// Checks the drive `path` is on has `required` bytes free, counting `freed` bytes that will be
+/// deleted before writing. If the free space can't be read the write is let through.
+pub fn check_free_space(
+    path: &Path,
+    required: u64,
+    freed: u64,
+) -> Result<(), InsufficientSpaceError> {
+    check_space(path, required, freed, 0)
+}
+
+// Like check_free_space, with `held` bytes of the free space already promised to someone else
+fn check_space(
+    path: &Path,
+    required: u64,
+    freed: u64,
+    held: u64,
+) -> Result<(), InsufficientSpaceError> {
+    // The folder may not exist yet, so ask about the closest one that does
+    let Some(existing) = path.ancestors().find(|ancestor| ancestor.exists()) else {
+        return Ok(());
+    };
+    let available = match fs4::available_space(existing) {
+        Ok(available) => available.saturating_add(freed).saturating_sub(held),
+        Err(err) => {
+            eprintln!("Failed to read free space of {:?}: {}", existing, err);
+            return Ok(());
+        }
+    };
+    if required > available {
+        Err(InsufficientSpaceError {
+            path: path.to_path_buf(),
+            required,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+// Room promised to extractions that are running, so several of them checking at once don't all
+// count the same free space. Archives are extracted into the mods folder, so every reservation is
+// taken to be on the same drive. What an extraction has written so far already shows in the free
+// space, so only the rest of it is held back.
+#[derive(Debug, Default, Clone)]
+pub struct SpaceReservations(Arc<Mutex<Vec<Reserved>>>);
+
+#[derive(Debug)]
+struct Reserved {
+    bytes: u64,
+    progress: Arc<ExtractProgress>,
+}
+
+// Gives the space back when dropped, however the extraction ended
+pub struct SpaceReservation {
+    reservations: SpaceReservations,
+    progress: Arc<ExtractProgress>,
+}
+
+impl Drop for SpaceReservation {
+    fn drop(&mut self) {
+        // Also runs while a panicking extraction unwinds, which mustn't turn into an abort
+        self.reservations
+            .lock()
+            .retain(|reserved| !Arc::ptr_eq(&reserved.progress, &self.progress));
+    }
+}
+
+impl SpaceReservations {
+    // The list stays consistent even if a thread panicked while holding it
+    fn lock(&self) -> MutexGuard<'_, Vec<Reserved>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+    /// Checks there's room for `required` bytes besides what other extractions still have to
+    /// write, and holds it until the returned reservation is dropped. `progress` is how much of
+    /// it has been written.
+    pub fn reserve(
+        &self,
+        path: &Path,
+        required: u64,
+        progress: Arc<ExtractProgress>,
+    ) -> Result<SpaceReservation, InsufficientSpaceError> {
+        // Held while checking, so two extractions can't both see the space as free
+        let mut reserved = self.lock();
+        let held = reserved
+            .iter()
+            .map(|reserved| {
+                let written = reserved.progress.bytes_written.load(Ordering::Relaxed);
+                reserved.bytes.saturating_sub(written)
+            })
+            .sum();
+        check_space(path, required, 0, held)?;
+        reserved.push(Reserved {
+            bytes: required,
+            progress: Arc::clone(&progress),
+        });
+        Ok(SpaceReservation {
+            reservations: self.clone(),
+            progress,
+        })
+    }
+}
+
+// Adds up the size of every file below `path`, what deleting it would free
+pub fn size_on_disk(path: &Path) -> u64 {
+    match path.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => path
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| size_on_disk(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
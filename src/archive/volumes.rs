@@ -0,0 +1,302 @@
+use crate::archive::ArchiveKind;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+
+// Split archives come either as raw pieces of one file (name.7z.001, name.7z.002, ...) or as
+// spanned zips, where the last piece keeps the .zip name (name.z01, name.z02, ..., name.zip)
+fn split_volume_number(path: &Path) -> Option<(String, u32)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (stem, number) = file_name.rsplit_once('.')?;
+    if number.len() != 3 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    ArchiveKind::from_path(Path::new(stem))?;
+    Some((stem.to_string(), number.parse().ok()?))
+}
+
+fn spanned_volume_number(path: &Path) -> Option<(String, u32)> {
+    let file_name = path.file_name()?.to_str()?;
+    let (stem, extension) = file_name.rsplit_once('.')?;
+    let number = extension
+        .strip_prefix('z')
+        .or(extension.strip_prefix('Z'))?;
+    if number.len() < 2 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((stem.to_string(), number.parse().ok()?))
+}
+
+fn spanned_part(last_part: &Path, stem: &str, number: u32) -> PathBuf {
+    last_part.with_file_name(format!("{}.z{:02}", stem, number))
+}
+
+pub fn is_volume(path: &Path) -> bool {
+    split_volume_number(path).is_some() || spanned_volume_number(path).is_some()
+}
+
+/// True for every piece of a split archive except the one extraction starts from.
+pub fn is_secondary_volume(path: &Path) -> bool {
+    match split_volume_number(path) {
+        Some((_, number)) => number != 1,
+        None => spanned_volume_number(path).is_some(),
+    }
+}
+
+/// Lists every piece of a split archive in order, starting from its first volume (.001) or
+/// the .zip that closes a spanned set. Returns None for ordinary archives.
+pub fn volume_parts(path: &Path) -> Option<Vec<PathBuf>> {
+    if let Some((stem, 1)) = split_volume_number(path) {
+        let mut parts = Vec::new();
+        let mut number = 1;
+        loop {
+            let part = path.with_file_name(format!("{}.{:03}", stem, number));
+            if !part.exists() {
+                break;
+            }
+            parts.push(part);
+            number += 1;
+        }
+        return Some(parts);
+    }
+    if ArchiveKind::from_path(path) == Some(ArchiveKind::Zip) {
+        let stem = path.file_stem()?.to_str()?;
+        if !spanned_part(path, stem, 1).exists() {
+            return None;
+        }
+        let mut parts = Vec::new();
+        let mut number = 1;
+        while spanned_part(path, stem, number).exists() {
+            parts.push(spanned_part(path, stem, number));
+            number += 1;
+        }
+        parts.push(path.to_path_buf());
+        return Some(parts);
+    }
+    None
+}
+
+/// Reassembles a split archive into `destination`, which must carry the archive's real
+/// extension (e.g. `mod.7z`) so it can be extracted like any other archive.
+pub fn join_volumes(parts: &[PathBuf], destination: &Path) -> Result<(), Box<dyn Error>> {
+    let mut out_file = File::create(destination)?;
+    let mut disk_offsets = Vec::with_capacity(parts.len());
+    let mut offset = 0;
+    for part in parts {
+        disk_offsets.push(offset);
+        offset += io::copy(&mut File::open(part)?, &mut out_file)?;
+    }
+    drop(out_file);
+
+    let spanned = parts
+        .first()
+        .is_some_and(|part| spanned_volume_number(part).is_some());
+    if spanned {
+        rebase_spanned_zip(destination, &disk_offsets)?;
+    }
+    Ok(())
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+// Spanned zips record every offset relative to the start of the disk it lives on. Once the
+// disks are glued together those offsets have to point into the single joined file instead.
+fn rebase_spanned_zip(path: &Path, disk_offsets: &[u64]) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = file.metadata()?.len();
+
+    // The end of central directory record is 22 bytes plus a comment of at most 65535 bytes
+    let tail_len = len.min(22 + u16::MAX as u64);
+    let tail_start = len - tail_len;
+    let mut tail = vec![0; tail_len as usize];
+    file.seek(SeekFrom::Start(tail_start))?;
+    file.read_exact(&mut tail)?;
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&at| read_u32(&tail, at) == END_OF_CENTRAL_DIRECTORY)
+        .ok_or("Spanned zip has no end of central directory record")?;
+
+    let central_directory_disk = read_u16(&tail, eocd + 6) as usize;
+    let total_entries = read_u16(&tail, eocd + 10);
+    let central_directory_size = read_u32(&tail, eocd + 12);
+    let central_directory_offset = read_u32(&tail, eocd + 16);
+    if total_entries == u16::MAX || central_directory_offset == u32::MAX {
+        return Err("Spanned zip64 archives are not supported".into());
+    }
+    let disk_offset = |disk: usize| {
+        disk_offsets
+            .get(disk)
+            .copied()
+            .ok_or_else(|| format!("Spanned zip is missing volume {}", disk + 1))
+    };
+    let central_directory_start =
+        disk_offset(central_directory_disk)? + central_directory_offset as u64;
+
+    let mut central_directory = vec![0; central_directory_size as usize];
+    file.seek(SeekFrom::Start(central_directory_start))?;
+    file.read_exact(&mut central_directory)?;
+    let mut at = 0;
+    for _ in 0..total_entries {
+        if at + 46 > central_directory.len()
+            || read_u32(&central_directory, at) != CENTRAL_DIRECTORY_HEADER
+        {
+            return Err("Spanned zip has a corrupt central directory".into());
+        }
+        let disk = read_u16(&central_directory, at + 34) as usize;
+        let local_header_offset = disk_offset(disk)? + read_u32(&central_directory, at + 42) as u64;
+        let local_header_offset = u32::try_from(local_header_offset)
+            .map_err(|_| "Spanned zip is too large to join without zip64")?;
+        central_directory[at + 34..at + 36].copy_from_slice(&0u16.to_le_bytes());
+        central_directory[at + 42..at + 46].copy_from_slice(&local_header_offset.to_le_bytes());
+
+        let name_len = read_u16(&central_directory, at + 28) as usize;
+        let extra_len = read_u16(&central_directory, at + 30) as usize;
+        let comment_len = read_u16(&central_directory, at + 32) as usize;
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    file.seek(SeekFrom::Start(central_directory_start))?;
+    file.write_all(&central_directory)?;
+
+    // Everything now lives on a single disk
+    let central_directory_start = u32::try_from(central_directory_start)
+        .map_err(|_| "Spanned zip is too large to join without zip64")?;
+    tail[eocd + 4..eocd + 6].copy_from_slice(&0u16.to_le_bytes());
+    tail[eocd + 6..eocd + 8].copy_from_slice(&0u16.to_le_bytes());
+    tail[eocd + 8..eocd + 10].copy_from_slice(&total_entries.to_le_bytes());
+    tail[eocd + 16..eocd + 20].copy_from_slice(&central_directory_start.to_le_bytes());
+    file.seek(SeekFrom::Start(tail_start + eocd as u64))?;
+    file.write_all(&tail[eocd..eocd + 22])?;
+    Ok(())
+}
+
+// Name of the archive the volumes make up, e.g. "mod.7z" for "mod.7z.001"
+pub fn joined_file_name(first_part: &Path, last_part: &Path) -> Option<String> {
+    match split_volume_number(first_part) {
+        Some((stem, _)) => Some(stem),
+        None => Some(last_part.file_name()?.to_str()?.to_string()),
+    }
+}
+
+// Where to join volumes in the temp directory. Several archives with the same name can be
+// joined at once by the extraction pool or by another copy of the manager, so the path is made
+// unique to this process and call, keeping the name last so the extension still says the kind.
+pub(crate) fn joined_path(joined_name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let number = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("mrmm-{}-{}-{}", process::id(), number, joined_name))
+}
+
+pub(crate) fn remove_joined(path: &Path) {
+    if !path.exists() {
+        return;
+    }
+    fs::remove_file(path)
+        .unwrap_or_else(|err| eprintln!("Failed to remove joined archive {:?}: {}", path, err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+    use std::io::Cursor;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+    #[test]
+    fn split_volume_numbers() {
+        let number = |name: &str| split_volume_number(Path::new(name));
+        assert_eq!(number("Suit.7z.001"), Some(("Suit.7z".to_string(), 1)));
+        assert_eq!(
+            number("mods/Suit.zip.012"),
+            Some(("Suit.zip".to_string(), 12))
+        );
+        assert_eq!(number("Suit.7z.1"), None);
+        assert_eq!(number("Suit.7z.0001"), None);
+        assert_eq!(number("Suit.txt.001"), None);
+        assert_eq!(number("Suit.7z"), None);
+        assert!(is_secondary_volume(Path::new("Suit.7z.002")));
+        assert!(!is_secondary_volume(Path::new("Suit.7z.001")));
+        assert!(is_secondary_volume(Path::new("Suit.z01")));
+    }
+
+    fn write_u16(bytes: &mut [u8], at: usize, value: u16) {
+        bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(bytes: &mut [u8], at: usize, value: u32) {
+        bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    // Cuts a zip in two at the start of its second file, with the offsets of everything on the
+    // second disk made relative to it the way spanned zips record them
+    fn span_zip(zip: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut archive = ZipArchive::new(Cursor::new(zip)).unwrap();
+        let split = archive.by_index_raw(1).unwrap().header_start() as usize;
+        let mut zip = zip.to_vec();
+        let eocd = (0..zip.len() - 21)
+            .rev()
+            .find(|&at| read_u32(&zip, at) == END_OF_CENTRAL_DIRECTORY)
+            .unwrap();
+        let central_directory = read_u32(&zip, eocd + 16) as usize;
+        let mut at = central_directory;
+        for _ in 0..read_u16(&zip, eocd + 10) {
+            let offset = read_u32(&zip, at + 42) as usize;
+            if offset >= split {
+                write_u16(&mut zip, at + 34, 1);
+                write_u32(&mut zip, at + 42, (offset - split) as u32);
+            }
+            at += 46
+                + read_u16(&zip, at + 28) as usize
+                + read_u16(&zip, at + 30) as usize
+                + read_u16(&zip, at + 32) as usize;
+        }
+        write_u16(&mut zip, eocd + 4, 1);
+        write_u16(&mut zip, eocd + 6, 1);
+        write_u16(&mut zip, eocd + 8, 1);
+        write_u32(&mut zip, eocd + 16, (central_directory - split) as u32);
+        let second = zip.split_off(split);
+        (zip, second)
+    }
+
+    #[test]
+    fn joined_spanned_zip_points_into_the_single_file() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        for (name, contents) in [("Suit/Suit_P.pak", "first"), ("Suit/mod.json", "{}")] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let zip = writer.finish().unwrap().into_inner();
+
+        let dir = test_dir("spanned_zip");
+        let (first, second) = span_zip(&zip);
+        fs::write(dir.join("Suit.z01"), first).unwrap();
+        fs::write(dir.join("Suit.zip"), second).unwrap();
+        let parts = volume_parts(&dir.join("Suit.zip")).unwrap();
+        assert_eq!(parts, [dir.join("Suit.z01"), dir.join("Suit.zip")]);
+        let joined = dir.join("joined.zip");
+        join_volumes(&parts, &joined).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&joined).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name("Suit/mod.json")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "{}");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -7,6 +7,10 @@ use std::path::PathBuf;
 fn main() -> eframe::Result {
     let mut manager: ModManager = ModManager::new();
     manager.mod_directory = env::current_dir().unwrap().join("mods");
+    manager.settings_path = env::current_dir().unwrap().join("settings.json");
+    manager.settings = Settings::load(&manager.settings_path);
+    manager.keyring_path = env::current_dir().unwrap().join("trusted_authors.json");
+    manager.keyring = Keyring::load(&manager.keyring_path);
     manager.game_directory = find_game_dir();
     manager.init_mods();
     println!("{:?}", manager);
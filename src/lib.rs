@@ -1,2 +1,12 @@
+pub mod archive;
 pub mod mod_types;
 pub mod tools;
+
+// A fresh folder in the temp directory for one test, named after it since tests run in parallel
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("mrmm-test-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,219 @@
+use crate::mod_types::ModType;
+use schemars::JsonSchema;
+use semver::Version;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+/// Steam app id of Marvel Rivals.
+pub const APP_ID: u32 = 2767030;
+
+/// Version of this manager, compared against `min_manager_version`.
+pub const MANAGER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Steam build ids a mod is known to work with, both ends included
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct BuildRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u64>,
+}
+
+impl BuildRange {
+    pub fn contains(&self, build: u64) -> bool {
+        self.min.is_none_or(|min| build >= min) && self.max.is_none_or(|max| build <= max)
+    }
+}
+
+impl Display for BuildRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            (Some(min), Some(max)) => write!(f, "{} to {}", min, max),
+            (Some(min), None) => write!(f, "{} or newer", min),
+            (None, Some(max)) => write!(f, "{} or older", max),
+            (None, None) => write!(f, "any"),
+        }
+    }
+}
+
+// Which game builds and manager versions a mod works with. Flattened into the manifest.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Compatibility {
+    /// Steam build ids of Marvel Rivals the mod works with. Empty means any build.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub game_build: Vec<BuildRange>,
+    /// The oldest manager that understands this manifest, like "0.2.0"
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub min_manager_version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    GameBuild {
+        name: String,
+        installed: u64,
+        supported: Vec<BuildRange>,
+    },
+    ManagerVersion {
+        name: String,
+        required: String,
+    },
+}
+
+impl Display for Incompatibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Incompatibility::GameBuild {
+                name,
+                installed,
+                supported,
+            } => {
+                let supported: Vec<String> = supported.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "{} supports game builds {}, but build {} is installed",
+                    name,
+                    supported.join(", "),
+                    installed
+                )
+            }
+            Incompatibility::ManagerVersion { name, required } => write!(
+                f,
+                "{} needs mod manager {} or newer, this is {}",
+                name, required, MANAGER_VERSION
+            ),
+        }
+    }
+}
+
+impl Compatibility {
+    pub fn has_valid_version(&self) -> bool {
+        self.min_manager_version.is_empty() || Version::parse(&self.min_manager_version).is_ok()
+    }
+    // What keeps the mod from working, `game_build` being None when the installed build is unknown
+    pub fn check(&self, name: &str, game_build: Option<u64>) -> Vec<Incompatibility> {
+        let mut problems = Vec::new();
+        if let Some(installed) = game_build {
+            if !self.game_build.is_empty()
+                && !self
+                    .game_build
+                    .iter()
+                    .any(|range| range.contains(installed))
+            {
+                problems.push(Incompatibility::GameBuild {
+                    name: name.to_string(),
+                    installed,
+                    supported: self.game_build.clone(),
+                });
+            }
+        }
+        if let (Ok(required), Ok(current)) = (
+            Version::parse(&self.min_manager_version),
+            Version::parse(MANAGER_VERSION),
+        ) {
+            if required > current {
+                problems.push(Incompatibility::ManagerVersion {
+                    name: name.to_string(),
+                    required: self.min_manager_version.clone(),
+                });
+            }
+        }
+        problems
+    }
+}
+
+/// Checks every mod against the installed game build, returning the index of each one that
+/// won't work along with why.
+pub fn check_compatibility(
+    mods: &[ModType],
+    game_build: Option<u64>,
+) -> Vec<(usize, Incompatibility)> {
+    let mut problems = Vec::new();
+    for (index, modification) in mods.iter().enumerate() {
+        if let Some(compatibility) = modification.compatibility() {
+            for problem in compatibility.check(modification.name(), game_build) {
+                problems.push((index, problem));
+            }
+        }
+    }
+    problems
+}
+
+/// Reads the installed build id from Steam's appmanifest, which sits in the steamapps folder
+/// two levels above the game directory.
+pub fn installed_game_build(game_directory: &Path) -> Result<u64, String> {
+    let manifest_path = game_directory
+        .parent()
+        .and_then(Path::parent)
+        .map(|steamapps| steamapps.join(format!("appmanifest_{}.acf", APP_ID)))
+        .ok_or_else(|| format!("{:?} is not inside a Steam library", game_directory))?;
+    let content = fs::read_to_string(&manifest_path)
+        .map_err(|err| format!("Failed to read {:?}: {}", manifest_path, err))?;
+    // Lines look like: "buildid"		"16969613"
+    for line in content.lines() {
+        let quoted: Vec<&str> = line.split('"').skip(1).step_by(2).collect();
+        if let ["buildid", build] = quoted[..] {
+            return build.parse().map_err(|err| {
+                format!("Invalid buildid {} in {:?}: {}", build, manifest_path, err)
+            });
+        }
+    }
+    Err(format!("No buildid in {:?}", manifest_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+
+    #[test]
+    fn build_ranges_include_both_ends() {
+        let range = BuildRange {
+            min: Some(100),
+            max: Some(200),
+        };
+        assert!(!range.contains(99));
+        assert!(range.contains(100));
+        assert!(range.contains(200));
+        assert!(!range.contains(201));
+        let newer = BuildRange {
+            min: Some(100),
+            max: None,
+        };
+        assert!(newer.contains(u64::MAX));
+        assert!(!newer.contains(99));
+        assert!(BuildRange::default().contains(0));
+    }
+
+    #[test]
+    fn installed_build_is_read_from_the_appmanifest() {
+        let steamapps = test_dir("appmanifest");
+        let game_directory = steamapps.join("common").join("MarvelRivals");
+        fs::create_dir_all(&game_directory).unwrap();
+        let manifest_path = steamapps.join(format!("appmanifest_{}.acf", APP_ID));
+        assert!(installed_game_build(&game_directory).is_err());
+
+        fs::write(
+            &manifest_path,
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"2767030\"\n\t\"buildid\"\t\t\"16969613\"\n}\n",
+        )
+        .unwrap();
+        assert_eq!(installed_game_build(&game_directory), Ok(16969613));
+
+        fs::write(
+            &manifest_path,
+            "\"AppState\"\n{\n\t\"buildid\"\t\t\"latest\"\n}\n",
+        )
+        .unwrap();
+        assert!(installed_game_build(&game_directory).is_err());
+        fs::write(&manifest_path, "\"AppState\"\n{\n}\n").unwrap();
+        assert!(installed_game_build(&game_directory).is_err());
+        fs::remove_dir_all(steamapps).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+use crate::mod_types::ModType;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyProblem {
+    // Nothing in the mods folder has the required id
+    Missing {
+        name: String,
+        requires: String,
+    },
+    // The required mod is there but not enabled
+    Disabled {
+        name: String,
+        requires: String,
+    },
+    Conflict {
+        name: String,
+        other: String,
+    },
+    // Both mods fill a slot that only takes one
+    Exclusive {
+        name: String,
+        other: String,
+        slot: String,
+    },
+}
+
+impl Display for DependencyProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencyProblem::Missing { name, requires } => {
+                write!(f, "{} requires {}, which is not installed", name, requires)
+            }
+            DependencyProblem::Disabled { name, requires } => {
+                write!(f, "{} requires {}, which is not enabled", name, requires)
+            }
+            DependencyProblem::Conflict { name, other } => {
+                write!(f, "{} conflicts with {}", name, other)
+            }
+            DependencyProblem::Exclusive { name, other, slot } => {
+                write!(f, "{} and {} both replace {}", name, other, slot)
+            }
+        }
+    }
+}
+
+// Either side declaring the conflict is enough
+pub(crate) fn conflicts(a: &ModType, b: &ModType) -> bool {
+    let declared = |a: &ModType, b: &ModType| {
+        b.id()
+            .is_some_and(|id| a.conflicts_with().iter().any(|other| other == id))
+    };
+    declared(a, b) || declared(b, a)
+}
+
+pub(crate) fn shared_slot<'a>(a: &'a ModType, b: &ModType) -> Option<&'a str> {
+    a.exclusive()
+        .iter()
+        .find(|slot| b.exclusive().contains(slot))
+        .map(String::as_str)
+}
+
+pub(crate) fn find_by_id<'a>(mods: &'a [ModType], id: &str) -> Option<(usize, &'a ModType)> {
+    mods.iter()
+        .enumerate()
+        .find(|(_, modification)| modification.id() == Some(id))
+}
+
+/// Checks the enabled mods for requirements that aren't met and for conflicts between them.
+pub fn check_dependencies(mods: &[ModType]) -> Vec<DependencyProblem> {
+    let mut problems = Vec::new();
+    let enabled: Vec<(usize, &ModType)> = mods
+        .iter()
+        .enumerate()
+        .filter(|(_, modification)| modification.is_enabled())
+        .collect();
+    for (index, modification) in &enabled {
+        for requires in modification.requires() {
+            match find_by_id(mods, requires) {
+                Some((_, required)) if required.is_enabled() => {}
+                Some((_, required)) => problems.push(DependencyProblem::Disabled {
+                    name: modification.name().to_string(),
+                    requires: required.name().to_string(),
+                }),
+                None => problems.push(DependencyProblem::Missing {
+                    name: modification.name().to_string(),
+                    requires: requires.clone(),
+                }),
+            }
+        }
+        // Each pair is only reported once
+        for (other_index, other) in &enabled {
+            if other_index <= index {
+                continue;
+            }
+            if conflicts(modification, other) {
+                problems.push(DependencyProblem::Conflict {
+                    name: modification.name().to_string(),
+                    other: other.name().to_string(),
+                });
+            }
+            if let Some(slot) = shared_slot(modification, other) {
+                problems.push(DependencyProblem::Exclusive {
+                    name: modification.name().to_string(),
+                    other: other.name().to_string(),
+                    slot: slot.to_string(),
+                });
+            }
+        }
+    }
+    problems
+}
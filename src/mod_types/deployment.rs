@@ -0,0 +1,361 @@
+use crate::mod_types::{deploy_file, hash_file, DeploymentReport, ModSource};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Name of the record of what was copied into ~mods, kept next to the paks.
+pub const DEPLOYMENT_FILE: &str = "mrmm_deployment.json";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeployedFile {
+    pub mod_name: String,
+    // Where the file was copied from, for messages
+    pub source: PathBuf,
+    // The folder or archive of the mod and the file's path within it, to copy it again from
+    pub origin: PathBuf,
+    pub entry: PathBuf,
+    // The name the mod ships the file with
+    pub file_name: String,
+    // The name it was given in ~mods, different from file_name when another mod had it first
+    pub deployed_name: String,
+    // What the copy looked like right after deploying, to notice it changing later
+    pub size: u64,
+    pub modified: u64,
+    pub hash: String,
+}
+
+impl DeployedFile {
+    pub fn is_renamed(&self) -> bool {
+        self.file_name != self.deployed_name
+    }
+}
+
+// How ~mods differs from what was deployed to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+    Missing { file: String, mod_name: String },
+    Modified { file: String, mod_name: String },
+    // Put there by something other than the manager
+    Extra { file: String },
+}
+
+impl Display for Drift {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drift::Missing { file, mod_name } => write!(f, "{} from {} is missing", file, mod_name),
+            Drift::Modified { file, mod_name } => {
+                write!(f, "{} from {} was changed", file, mod_name)
+            }
+            Drift::Extra { file } => write!(f, "{} wasn't put there by the manager", file),
+        }
+    }
+}
+
+fn modified_secs(metadata: &Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+// Only files the game picks up from ~mods count as extra, anything else (readmes, folders
+// other tools keep there) doesn't change what gets loaded
+fn is_game_file(name: &str) -> bool {
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    matches!(extension.as_deref(), Some("pak" | "utoc" | "ucas" | "sig"))
+}
+
+// Everything the manager put in ~mods the last time mods were loaded, in load order
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Deployment {
+    pub files: Vec<DeployedFile>,
+    // Lowercased deployed names, Windows doesn't tell file names apart by case
+    #[serde(skip)]
+    taken: HashSet<String>,
+}
+
+// "pakchunk99-Windows_P.pak" becomes "pakchunk99-Windows~02_P.pak". The suffix goes before
+// "_P" so the game still treats it as a patch pak, and '~' sorts after '_' so the copy loads
+// right after the file it collided with. Like the game, "_p" counts as the suffix too.
+fn numbered_name(file_name: &str, number: usize) -> String {
+    let (stem, extension) = match file_name.rfind('.') {
+        Some(index) => file_name.split_at(index),
+        None => (file_name, ""),
+    };
+    let suffix_at = stem.len().saturating_sub(2);
+    match stem.get(suffix_at..) {
+        Some(suffix) if suffix.eq_ignore_ascii_case("_P") => {
+            format!(
+                "{}~{:02}{}{}",
+                &stem[..suffix_at],
+                number,
+                suffix,
+                extension
+            )
+        }
+        _ => format!("{}~{:02}{}", stem, number, extension),
+    }
+}
+
+impl Deployment {
+    /// Reads the record left in `mods_dir`, None if mods were never loaded by this version.
+    pub fn load(mods_dir: &Path) -> Option<Self> {
+        let path = mods_dir.join(DEPLOYMENT_FILE);
+        let json = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str::<Self>(&json) {
+            Ok(mut deployment) => {
+                deployment.taken = deployment
+                    .files
+                    .iter()
+                    .map(|file| file.deployed_name.to_lowercase())
+                    .collect();
+                Some(deployment)
+            }
+            Err(err) => {
+                eprintln!("Failed to parse {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+    pub fn save(&self, mods_dir: &Path) -> Result<(), String> {
+        let path = mods_dir.join(DEPLOYMENT_FILE);
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(&path, json).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+    }
+    /// Adds a file to deploy, returning the name it gets in ~mods. Files are added in load
+    /// order, the first to claim a name keeps it and later ones are numbered.
+    pub fn add(&mut self, mod_name: &str, source: &ModSource, entry: &Path) -> String {
+        let file_name = &entry
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let mut deployed_name = file_name.to_string();
+        let mut number = 2;
+        while self.taken.contains(&deployed_name.to_lowercase())
+            || deployed_name.eq_ignore_ascii_case(DEPLOYMENT_FILE)
+        {
+            deployed_name = numbered_name(file_name, number);
+            number += 1;
+        }
+        self.taken.insert(deployed_name.to_lowercase());
+        self.files.push(DeployedFile {
+            mod_name: mod_name.to_string(),
+            source: source.display_path(entry),
+            origin: source.path().clone(),
+            entry: entry.to_path_buf(),
+            file_name: file_name.to_string(),
+            deployed_name: deployed_name.clone(),
+            ..Default::default()
+        });
+        deployed_name
+    }
+    /// Notes the size, time and hash of every file once it's been copied.
+    pub fn record_files(&mut self, mods_dir: &Path) -> Result<(), String> {
+        for file in &mut self.files {
+            let path = mods_dir.join(&file.deployed_name);
+            let metadata =
+                fs::metadata(&path).map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+            file.size = metadata.len();
+            file.modified = modified_secs(&metadata);
+            file.hash = hash_file(&path)?;
+        }
+        Ok(())
+    }
+    /// Compares `mods_dir` against the record. Files are only hashed again when their size is
+    /// the same but their modified time isn't.
+    pub fn check_drift(&self, mods_dir: &Path) -> Vec<Drift> {
+        let mut drift = Vec::new();
+        for file in &self.files {
+            let path = mods_dir.join(&file.deployed_name);
+            let changed = match fs::metadata(&path) {
+                Err(_) => {
+                    drift.push(Drift::Missing {
+                        file: file.deployed_name.clone(),
+                        mod_name: file.mod_name.clone(),
+                    });
+                    continue;
+                }
+                // Records from before sizes and hashes were kept can only tell what's missing
+                Ok(_) if file.hash.is_empty() => false,
+                Ok(metadata) if metadata.len() != file.size => true,
+                Ok(metadata) if modified_secs(&metadata) == file.modified => false,
+                Ok(_) => hash_file(&path).map_or(true, |hash| hash != file.hash),
+            };
+            if changed {
+                drift.push(Drift::Modified {
+                    file: file.deployed_name.clone(),
+                    mod_name: file.mod_name.clone(),
+                });
+            }
+        }
+        if let Ok(entries) = fs::read_dir(mods_dir) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_file = entry.file_type().is_ok_and(|file_type| file_type.is_file());
+                if is_file && is_game_file(&name) && !self.taken.contains(&name.to_lowercase()) {
+                    drift.push(Drift::Extra { file: name });
+                }
+            }
+        }
+        drift
+    }
+    /// Copies every recorded file into `mods_dir` again from the mod it came from, putting ~mods
+    /// back the way it was deployed. Returns the files that couldn't be restored.
+    pub fn restore(
+        &mut self,
+        mods_dir: &Path,
+        verify: bool,
+        report: &mut DeploymentReport,
+    ) -> Vec<String> {
+        let mut failed = Vec::new();
+        let mut sources: HashMap<PathBuf, Result<ModSource, String>> = HashMap::new();
+        for file in &self.files {
+            // Records from before the origin was kept don't say where to copy from
+            if file.origin.as_os_str().is_empty() {
+                failed.push(format!(
+                    "{}: not recorded where it came from",
+                    file.deployed_name
+                ));
+                continue;
+            }
+            let source = sources.entry(file.origin.clone()).or_insert_with(|| {
+                if file.origin.is_dir() {
+                    Ok(ModSource::Directory(file.origin.clone()))
+                } else {
+                    ModSource::open_archive(file.origin.clone())
+                }
+            });
+            let restored = source.as_ref().map_err(Clone::clone).and_then(|source| {
+                let destination = mods_dir.join(&file.deployed_name);
+                deploy_file(source, &file.entry, &destination, verify, report)
+            });
+            if let Err(err) = restored {
+                failed.push(format!("{}: {}", file.deployed_name, err));
+            }
+        }
+        if let Err(err) = self
+            .record_files(mods_dir)
+            .and_then(|_| self.save(mods_dir))
+        {
+            failed.push(err);
+        }
+        failed
+    }
+    // How much restoring would write
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+    pub fn renamed(&self) -> impl Iterator<Item = &DeployedFile> {
+        self.files.iter().filter(|file| file.is_renamed())
+    }
+    // How much removing the deployed files would free
+    pub fn size_on_disk(&self, mods_dir: &Path) -> u64 {
+        self.files
+            .iter()
+            .filter_map(|file| fs::metadata(mods_dir.join(&file.deployed_name)).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+    /// Removes the files that were deployed and the record of them, leaving anything else in
+    /// `mods_dir` alone.
+    pub fn remove(&self, mods_dir: &Path) {
+        for file in &self.files {
+            let path = mods_dir.join(&file.deployed_name);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .unwrap_or_else(|err| eprintln!("Failed to remove {:?}: {}", path, err));
+            }
+        }
+        let _ = fs::remove_file(mods_dir.join(DEPLOYMENT_FILE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+
+    #[test]
+    fn numbered_names_keep_the_patch_suffix() {
+        assert_eq!(
+            numbered_name("pakchunk99-Windows_P.pak", 2),
+            "pakchunk99-Windows~02_P.pak"
+        );
+        assert_eq!(numbered_name("suit_p.pak", 2), "suit~02_p.pak");
+        assert_eq!(numbered_name("Suit.pak", 3), "Suit~03.pak");
+        assert_eq!(numbered_name("Suit", 12), "Suit~12");
+    }
+
+    #[test]
+    fn colliding_names_are_numbered_in_load_order() {
+        let source = ModSource::Directory(PathBuf::from("Suit"));
+        let mut deployment = Deployment::default();
+        assert_eq!(
+            deployment.add("Suit", &source, Path::new("Suit_P.pak")),
+            "Suit_P.pak"
+        );
+        assert_eq!(
+            deployment.add("Other", &source, Path::new("options/suit_p.pak")),
+            "suit~02_p.pak"
+        );
+        assert_eq!(
+            deployment.add("Record", &source, Path::new(DEPLOYMENT_FILE)),
+            "mrmm_deployment~02.json"
+        );
+    }
+
+    #[test]
+    fn check_drift_reports_what_changed() {
+        let dir = test_dir("check_drift");
+        let (mod_dir, mods_dir) = (dir.join("Suit"), dir.join("~mods"));
+        fs::create_dir_all(&mod_dir).unwrap();
+        fs::create_dir_all(mods_dir.join("Folder")).unwrap();
+        let source = ModSource::Directory(mod_dir);
+        let mut deployment = Deployment::default();
+        for name in ["Suit_P.pak", "Mask_P.pak", "Cape_P.pak"] {
+            let deployed_name = deployment.add("Suit", &source, Path::new(name));
+            fs::write(mods_dir.join(deployed_name), name).unwrap();
+        }
+        deployment.record_files(&mods_dir).unwrap();
+        deployment.save(&mods_dir).unwrap();
+        assert!(deployment.check_drift(&mods_dir).is_empty());
+
+        fs::remove_file(mods_dir.join("Mask_P.pak")).unwrap();
+        fs::write(mods_dir.join("Cape_P.pak"), "changed").unwrap();
+        fs::write(mods_dir.join("Extra_P.pak"), "extra").unwrap();
+        fs::write(mods_dir.join("Extra_P.utoc"), "extra").unwrap();
+        fs::write(mods_dir.join("readme.txt"), "not loaded").unwrap();
+        let mut drift = deployment.check_drift(&mods_dir);
+        drift.sort_by_key(ToString::to_string);
+        assert_eq!(
+            drift,
+            [
+                Drift::Modified {
+                    file: "Cape_P.pak".to_string(),
+                    mod_name: "Suit".to_string()
+                },
+                Drift::Extra {
+                    file: "Extra_P.pak".to_string()
+                },
+                Drift::Extra {
+                    file: "Extra_P.utoc".to_string()
+                },
+                Drift::Missing {
+                    file: "Mask_P.pak".to_string(),
+                    mod_name: "Suit".to_string()
+                },
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
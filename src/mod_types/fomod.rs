@@ -0,0 +1,924 @@
+use crate::archive;
+use crate::mod_types::manifest::ManifestFormat;
+use crate::mod_types::{
+    manifest, schema, GroupKind, ModOption, ModSource, Modification, OptionGroup, OptionGroups,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// FOMOD installers ship a fomod/ModuleConfig.xml describing install steps made of option
+// groups, with files that get installed depending on what the user picks.
+// See https://fomod-docs.readthedocs.io for the format.
+
+pub const FOMOD_CONFIG: &str = "fomod/ModuleConfig.xml";
+const FOMOD_INFO: &str = "fomod/info.xml";
+
+// Whether an archive entry is an installer config. Authors don't agree on the capitalisation.
+pub fn is_fomod_config(entry: &str) -> bool {
+    let mut parts = entry.rsplit('/');
+    parts
+        .next()
+        .is_some_and(|name| name.eq_ignore_ascii_case("ModuleConfig.xml"))
+        && parts
+            .next()
+            .is_some_and(|folder| folder.eq_ignore_ascii_case("fomod"))
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Order {
+    #[default]
+    Ascending,
+    Descending,
+    Explicit,
+}
+
+impl Order {
+    fn sort<T>(self, items: &mut [T], name: impl Fn(&T) -> &str) {
+        match self {
+            Order::Ascending => items.sort_by(|a, b| name(a).cmp(name(b))),
+            Order::Descending => items.sort_by(|a, b| name(b).cmp(name(a))),
+            Order::Explicit => {}
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Operator {
+    #[default]
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum FileState {
+    Missing,
+    Inactive,
+    Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum GroupType {
+    SelectAtLeastOne,
+    SelectAtMostOne,
+    SelectExactlyOne,
+    SelectAll,
+    SelectAny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PluginType {
+    Required,
+    Optional,
+    Recommended,
+    NotUsable,
+    CouldBeUsable,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Dependencies {
+    #[serde(rename = "@operator", default)]
+    pub operator: Operator,
+    #[serde(rename = "$value", default)]
+    pub conditions: Vec<Dependency>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Dependency {
+    FileDependency {
+        #[serde(rename = "@file")]
+        file: String,
+        #[serde(rename = "@state")]
+        state: FileState,
+    },
+    FlagDependency {
+        #[serde(rename = "@flag")]
+        flag: String,
+        #[serde(rename = "@value", default)]
+        value: String,
+    },
+    GameDependency {
+        #[serde(rename = "@version")]
+        version: String,
+    },
+    FommDependency {
+        #[serde(rename = "@version")]
+        version: String,
+    },
+    Dependencies(Dependencies),
+}
+
+impl Dependencies {
+    pub fn is_met(&self, flags: &HashMap<String, String>) -> bool {
+        let mut results = self.conditions.iter().map(|condition| match condition {
+            // File dependencies point at plugins of Bethesda games, which never exist here
+            Dependency::FileDependency { state, .. } => *state == FileState::Missing,
+            Dependency::FlagDependency { flag, value } => {
+                flags.get(flag).map(String::as_str).unwrap_or("") == value
+            }
+            // There are no game or mod manager versions to compare against
+            Dependency::GameDependency { .. } | Dependency::FommDependency { .. } => true,
+            Dependency::Dependencies(dependencies) => dependencies.is_met(flags),
+        });
+        match self.operator {
+            Operator::And => results.all(|met| met),
+            Operator::Or => results.any(|met| met),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileEntry {
+    #[serde(rename = "@source")]
+    pub source: String,
+    #[serde(rename = "@destination")]
+    pub destination: Option<String>,
+    #[serde(rename = "@priority", default)]
+    pub priority: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileItem {
+    File(FileEntry),
+    Folder(FileEntry),
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct FileList {
+    #[serde(rename = "$value", default)]
+    pub items: Vec<FileItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageRef {
+    #[serde(rename = "@path")]
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flag {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ConditionFlags {
+    #[serde(default)]
+    pub flag: Vec<Flag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TypeName {
+    #[serde(rename = "@name")]
+    pub name: PluginType,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TypePattern {
+    pub dependencies: Dependencies,
+    #[serde(rename = "type")]
+    pub plugin_type: TypeName,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct TypePatterns {
+    #[serde(default)]
+    pub pattern: Vec<TypePattern>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyType {
+    pub default_type: TypeName,
+    #[serde(default)]
+    pub patterns: TypePatterns,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDescriptor {
+    #[serde(rename = "type")]
+    pub plugin_type: Option<TypeName>,
+    pub dependency_type: Option<DependencyType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Plugin {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub image: Option<ImageRef>,
+    #[serde(default)]
+    pub files: FileList,
+    #[serde(default)]
+    pub condition_flags: ConditionFlags,
+    pub type_descriptor: TypeDescriptor,
+}
+
+impl Plugin {
+    // The type can depend on flags set by earlier steps
+    pub fn plugin_type(&self, flags: &HashMap<String, String>) -> PluginType {
+        if let Some(type_name) = &self.type_descriptor.plugin_type {
+            return type_name.name;
+        }
+        match &self.type_descriptor.dependency_type {
+            Some(dependency_type) => dependency_type
+                .patterns
+                .pattern
+                .iter()
+                .find(|pattern| pattern.dependencies.is_met(flags))
+                .map(|pattern| pattern.plugin_type.name)
+                .unwrap_or(dependency_type.default_type.name),
+            None => PluginType::Optional,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Plugins {
+    #[serde(rename = "@order", default)]
+    pub order: Order,
+    #[serde(default)]
+    pub plugin: Vec<Plugin>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Group {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@type")]
+    pub group_type: GroupType,
+    #[serde(default)]
+    pub plugins: Plugins,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Groups {
+    #[serde(rename = "@order", default)]
+    pub order: Order,
+    #[serde(default)]
+    pub group: Vec<Group>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallStep {
+    #[serde(rename = "@name")]
+    pub name: String,
+    pub visible: Option<Dependencies>,
+    #[serde(default)]
+    pub optional_file_groups: Groups,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallSteps {
+    #[serde(rename = "@order", default)]
+    pub order: Order,
+    #[serde(default)]
+    pub install_step: Vec<InstallStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FilePattern {
+    pub dependencies: Dependencies,
+    #[serde(default)]
+    pub files: FileList,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct FilePatterns {
+    #[serde(default)]
+    pub pattern: Vec<FilePattern>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ConditionalFileInstalls {
+    #[serde(default)]
+    pub patterns: FilePatterns,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleConfig {
+    #[serde(default)]
+    pub module_name: String,
+    pub module_image: Option<ImageRef>,
+    pub module_dependencies: Option<Dependencies>,
+    #[serde(default)]
+    pub required_install_files: FileList,
+    #[serde(default)]
+    pub install_steps: InstallSteps,
+    #[serde(default)]
+    pub conditional_file_installs: ConditionalFileInstalls,
+}
+
+impl ModuleConfig {
+    pub fn from_xml(xml: &str) -> Result<Self, String> {
+        let mut config: ModuleConfig =
+            quick_xml::de::from_str(xml).map_err(|err| err.to_string())?;
+        // Apply the declared ordering once so the wizard can show everything as is
+        let steps = &mut config.install_steps;
+        steps
+            .order
+            .sort(&mut steps.install_step, |step| step.name.as_str());
+        for step in &mut steps.install_step {
+            let groups = &mut step.optional_file_groups;
+            groups
+                .order
+                .sort(&mut groups.group, |group| group.name.as_str());
+            for group in &mut groups.group {
+                let plugins = &mut group.plugins;
+                plugins
+                    .order
+                    .sort(&mut plugins.plugin, |plugin| plugin.name.as_str());
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Optional fomod/info.xml with the details shown on Nexus
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ModuleInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+// FOMOD files are frequently saved as UTF-16 by the Windows tools that make them
+fn decode_xml(bytes: &[u8]) -> Result<String, String> {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|err| err.to_string())
+    };
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|err| err.to_string())
+        }
+        _ => String::from_utf8(bytes.to_vec()).map_err(|err| err.to_string()),
+    }
+}
+
+// A group of the installer as it becomes an option group, with the plugins that can be picked
+// and whether they were
+struct PlannedGroup<'a> {
+    kind: GroupKind,
+    group: &'a Group,
+    plugins: Vec<(&'a Plugin, bool)>,
+}
+
+// Walks the user through the install steps of a FOMOD and turns their picks into a regular mod
+#[derive(Debug)]
+pub struct FomodInstaller {
+    pub source: ModSource,
+    pub config: ModuleConfig,
+    pub info: ModuleInfo,
+    pub step: usize,
+    // Selected state of every plugin, indexed by step, group and plugin
+    selections: Vec<Vec<Vec<bool>>>,
+    visited: Vec<bool>,
+    // Lowercased paths to the real ones, FOMOD paths are case insensitive like Windows
+    files: HashMap<String, PathBuf>,
+}
+
+impl FomodInstaller {
+    // Finds the installer config in the mod, whatever its capitalisation
+    pub fn find_config(source: &ModSource) -> Option<PathBuf> {
+        source
+            .list_all(Path::new(""))
+            .into_iter()
+            .find(|file| is_fomod_config(&file.to_string_lossy().replace('\\', "/")))
+            .filter(|file| file.components().count() == 2)
+    }
+    pub fn open(source: ModSource) -> Result<Self, String> {
+        let config_path = Self::find_config(&source).ok_or_else(|| {
+            format!(
+                "No FOMOD installer found at {:?}",
+                source.display_path(Path::new(FOMOD_CONFIG))
+            )
+        })?;
+        let xml = decode_xml(&source.read_bytes(&config_path)?).map_err(|err| {
+            format!(
+                "{:?} is not readable: {}",
+                source.display_path(&config_path),
+                err
+            )
+        })?;
+        let config = ModuleConfig::from_xml(&xml).map_err(|err| {
+            format!(
+                "Failed to parse FOMOD config at {:?}: {}",
+                source.display_path(&config_path),
+                err
+            )
+        })?;
+
+        let files: HashMap<String, PathBuf> = source
+            .list_all(Path::new(""))
+            .into_iter()
+            .map(|file| (Self::normalize(&file.to_string_lossy()), file))
+            .collect();
+        let info = match files.get(&Self::normalize(FOMOD_INFO)) {
+            Some(info_path) => source
+                .read_bytes(info_path)
+                .and_then(|bytes| decode_xml(&bytes))
+                .and_then(|xml| quick_xml::de::from_str(&xml).map_err(|err| err.to_string()))
+                .unwrap_or_else(|err| {
+                    eprintln!("Ignoring unreadable FOMOD info {:?}: {}", info_path, err);
+                    ModuleInfo::default()
+                }),
+            None => ModuleInfo::default(),
+        };
+
+        let selections = config
+            .install_steps
+            .install_step
+            .iter()
+            .map(|step| {
+                step.optional_file_groups
+                    .group
+                    .iter()
+                    .map(|group| vec![false; group.plugins.plugin.len()])
+                    .collect()
+            })
+            .collect();
+        let mut installer = Self {
+            visited: vec![false; config.install_steps.install_step.len()],
+            source,
+            config,
+            info,
+            step: 0,
+            selections,
+            files,
+        };
+        installer.step = installer.next_visible(0).unwrap_or(0);
+        installer.enter_step();
+        Ok(installer)
+    }
+    fn normalize(path: &str) -> String {
+        path.replace('\\', "/").trim_matches('/').to_lowercase()
+    }
+    pub fn name(&self) -> &str {
+        if !self.config.module_name.is_empty() {
+            &self.config.module_name
+        } else if !self.info.name.is_empty() {
+            &self.info.name
+        } else {
+            self.source
+                .path()
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("FOMOD")
+        }
+    }
+    // Resolves a path from the config to the real file in the mod
+    pub fn resolve(&self, path: &str) -> Option<&PathBuf> {
+        self.files.get(&Self::normalize(path))
+    }
+    pub fn steps(&self) -> &[InstallStep] {
+        &self.config.install_steps.install_step
+    }
+    pub fn current_step(&self) -> Option<&InstallStep> {
+        self.steps().get(self.step)
+    }
+    // Flags set by the plugins picked in the visible steps before `step`
+    pub fn flags_before(&self, step: usize) -> HashMap<String, String> {
+        let mut flags = HashMap::new();
+        for index in 0..step.min(self.steps().len()) {
+            if !self.is_visible(index, &flags) {
+                continue;
+            }
+            for (group, selected) in self.steps()[index]
+                .optional_file_groups
+                .group
+                .iter()
+                .zip(&self.selections[index])
+            {
+                for (plugin, _) in group
+                    .plugins
+                    .plugin
+                    .iter()
+                    .zip(selected)
+                    .filter(|(_, selected)| **selected)
+                {
+                    for flag in &plugin.condition_flags.flag {
+                        flags.insert(flag.name.clone(), flag.value.clone());
+                    }
+                }
+            }
+        }
+        flags
+    }
+    fn is_visible(&self, step: usize, flags: &HashMap<String, String>) -> bool {
+        self.steps()[step]
+            .visible
+            .as_ref()
+            .is_none_or(|visible| visible.is_met(flags))
+    }
+    fn next_visible(&self, from: usize) -> Option<usize> {
+        (from..self.steps().len()).find(|&step| self.is_visible(step, &self.flags_before(step)))
+    }
+    fn previous_visible(&self) -> Option<usize> {
+        (0..self.step)
+            .rev()
+            .find(|&step| self.is_visible(step, &self.flags_before(step)))
+    }
+    pub fn has_previous(&self) -> bool {
+        self.previous_visible().is_some()
+    }
+    pub fn is_last_step(&self) -> bool {
+        self.next_visible(self.step + 1).is_none()
+    }
+    pub fn back(&mut self) {
+        if let Some(step) = self.previous_visible() {
+            self.step = step;
+        }
+    }
+    pub fn next(&mut self) {
+        if let Some(step) = self.next_visible(self.step + 1) {
+            self.step = step;
+            self.enter_step();
+        }
+    }
+    pub fn plugin_type(&self, group: usize, plugin: usize) -> PluginType {
+        let flags = self.flags_before(self.step);
+        self.steps()[self.step].optional_file_groups.group[group]
+            .plugins
+            .plugin[plugin]
+            .plugin_type(&flags)
+    }
+    // Preselects required and recommended plugins the first time a step is shown
+    fn enter_step(&mut self) {
+        if self.visited.get(self.step) != Some(&false) {
+            return;
+        }
+        self.visited[self.step] = true;
+        let flags = self.flags_before(self.step);
+        let step = &self.config.install_steps.install_step[self.step];
+        for (group, selected) in step
+            .optional_file_groups
+            .group
+            .iter()
+            .zip(&mut self.selections[self.step])
+        {
+            let types: Vec<PluginType> = group
+                .plugins
+                .plugin
+                .iter()
+                .map(|plugin| plugin.plugin_type(&flags))
+                .collect();
+            for (selected, plugin_type) in selected.iter_mut().zip(&types) {
+                *selected = group.group_type == GroupType::SelectAll
+                    || matches!(plugin_type, PluginType::Required | PluginType::Recommended);
+            }
+            let single = matches!(
+                group.group_type,
+                GroupType::SelectExactlyOne | GroupType::SelectAtMostOne
+            );
+            if single {
+                // Only keep the first preselection in a one-of group
+                if let Some(first) = selected.iter().position(|selected| *selected) {
+                    selected.fill(false);
+                    selected[first] = true;
+                }
+            }
+            if group.group_type == GroupType::SelectExactlyOne && !selected.contains(&true) {
+                if let Some(first) = types.iter().position(|t| *t != PluginType::NotUsable) {
+                    selected[first] = true;
+                }
+            }
+        }
+    }
+    pub fn is_selected(&self, group: usize, plugin: usize) -> bool {
+        self.selections[self.step][group][plugin]
+    }
+    // Whether the user may toggle this plugin at all
+    pub fn is_locked(&self, group: usize, plugin: usize) -> bool {
+        let group_type = self.steps()[self.step].optional_file_groups.group[group].group_type;
+        group_type == GroupType::SelectAll
+            || matches!(
+                self.plugin_type(group, plugin),
+                PluginType::Required | PluginType::NotUsable
+            )
+    }
+    pub fn set_selected(&mut self, group: usize, plugin: usize, selected: bool) {
+        if self.is_locked(group, plugin) {
+            return;
+        }
+        let group_type = self.steps()[self.step].optional_file_groups.group[group].group_type;
+        let group_selections = &mut self.selections[self.step][group];
+        match group_type {
+            GroupType::SelectExactlyOne if selected => {
+                group_selections.fill(false);
+                group_selections[plugin] = true;
+            }
+            // Deselecting the only choice would leave the group empty
+            GroupType::SelectExactlyOne => {}
+            GroupType::SelectAtMostOne if selected => {
+                group_selections.fill(false);
+                group_selections[plugin] = true;
+            }
+            _ => group_selections[plugin] = selected,
+        }
+    }
+    // Whether every group of the current step has an allowed number of plugins picked
+    pub fn is_step_complete(&self) -> bool {
+        let Some(step) = self.current_step() else {
+            return true;
+        };
+        step.optional_file_groups
+            .group
+            .iter()
+            .zip(&self.selections[self.step])
+            .all(|(group, selected)| {
+                let count = selected.iter().filter(|selected| **selected).count();
+                match group.group_type {
+                    GroupType::SelectExactlyOne => count == 1,
+                    GroupType::SelectAtMostOne => count <= 1,
+                    GroupType::SelectAtLeastOne => count >= 1,
+                    GroupType::SelectAll | GroupType::SelectAny => true,
+                }
+            })
+    }
+    // Whether a plugin is installed no matter what, going by the picks made in the wizard
+    fn is_always_installed(
+        group: &Group,
+        plugin: &Plugin,
+        flags: &HashMap<String, String>,
+    ) -> bool {
+        group.group_type == GroupType::SelectAll
+            || plugin.plugin_type(flags) == PluginType::Required
+    }
+    // Splits the installer into the files that are always installed, in priority order so later
+    // ones win, and the plugins of the steps shown for the user's picks, which become options.
+    // What the installer adds for a combination of picks is decided by the picks made here.
+    fn plan(&self) -> (Vec<(FileEntry, bool)>, Vec<PlannedGroup<'_>>) {
+        let mut always = Vec::new();
+        add_files(&self.config.required_install_files, &mut always);
+        let mut groups = Vec::new();
+        for (index, step) in self.steps().iter().enumerate() {
+            let flags = self.flags_before(index);
+            if !self.is_visible(index, &flags) {
+                continue;
+            }
+            for (group, selected) in step
+                .optional_file_groups
+                .group
+                .iter()
+                .zip(&self.selections[index])
+            {
+                let plugins: Vec<(&Plugin, bool)> = group
+                    .plugins
+                    .plugin
+                    .iter()
+                    .zip(selected.iter().copied())
+                    .collect();
+                let has_required = plugins
+                    .iter()
+                    .any(|(plugin, _)| Self::is_always_installed(group, plugin, &flags));
+                let kind = match group.group_type {
+                    GroupType::SelectExactlyOne => GroupKind::ExactlyOne,
+                    GroupType::SelectAtMostOne => GroupKind::AtMostOne,
+                    _ => GroupKind::Any,
+                };
+                let mut options = Vec::new();
+                for (plugin, selected) in plugins {
+                    if Self::is_always_installed(group, plugin, &flags) {
+                        add_files(&plugin.files, &mut always);
+                    } else if plugin.plugin_type(&flags) == PluginType::NotUsable
+                        || (has_required && kind != GroupKind::Any)
+                    {
+                        // Can't be picked, or a required plugin already fills the group
+                        continue;
+                    } else {
+                        options.push((plugin, selected));
+                    }
+                }
+                if !options.is_empty() {
+                    groups.push(PlannedGroup {
+                        kind,
+                        group,
+                        plugins: options,
+                    });
+                }
+            }
+        }
+        let flags = self.flags_before(self.steps().len());
+        for pattern in &self.config.conditional_file_installs.patterns.pattern {
+            if pattern.dependencies.is_met(&flags) {
+                add_files(&pattern.files, &mut always);
+            }
+        }
+        always.sort_by_key(|(entry, _)| entry.priority);
+        (always, groups)
+    }
+    // The files in the mod an entry of the config stands for, with where they go relative to
+    // the folder they are installed into
+    fn entry_files(&self, entry: &FileEntry, is_folder: bool) -> Vec<(PathBuf, String)> {
+        let destination = entry.destination.clone().unwrap_or(entry.source.clone());
+        if !is_folder {
+            return match self.resolve(&entry.source) {
+                Some(file) if destination.trim_matches(['/', '\\']).is_empty() => {
+                    let name = file.file_name().unwrap_or_default().to_string_lossy();
+                    vec![(file.clone(), name.to_string())]
+                }
+                Some(file) => vec![(file.clone(), destination)],
+                None => {
+                    eprintln!("FOMOD file {} not found in {}", entry.source, self.name());
+                    Vec::new()
+                }
+            };
+        }
+        let folder = Self::normalize(&entry.source);
+        let depth = Path::new(&folder).components().count();
+        self.files
+            .iter()
+            .filter(|(name, _)| folder.is_empty() || name.starts_with(&format!("{}/", folder)))
+            .map(|(_, file)| {
+                let inside: PathBuf = file.components().skip(depth).collect();
+                let path = Path::new(&destination).join(inside);
+                (file.clone(), path.to_string_lossy().to_string())
+            })
+            .collect()
+    }
+    // Copies the files into `folder` of the installed mod, returning the paks relative to the
+    // root of the mod. Paks go straight into the folder since only those are loaded from it,
+    // anything else keeps the place the installer gave it.
+    fn install_files(
+        &self,
+        files: &[(FileEntry, bool)],
+        output_dir: &Path,
+        folder: &Path,
+    ) -> Result<Vec<PathBuf>, String> {
+        let mut paks = Vec::new();
+        for (entry, is_folder) in files {
+            for (file, destination) in self.entry_files(entry, *is_folder) {
+                let is_pak = file
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("pak"));
+                let relative = if is_pak {
+                    folder.join(file.file_name().unwrap_or_default())
+                } else {
+                    archive::safe_join(folder, &destination).map_err(|err| err.to_string())?
+                };
+                let target = output_dir.join(&relative);
+                if is_pak && !paks.contains(&relative) {
+                    paks.push(relative);
+                }
+                if self.source.display_path(&file) == target {
+                    // Already where it needs to be
+                    continue;
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .map_err(|err| format!("Failed to create {:?}: {}", parent, err))?;
+                }
+                self.source.copy_to(&file, &target)?;
+            }
+        }
+        Ok(paks)
+    }
+    /// Installs the mod into `output_dir` and writes a mod.json for it, so the result loads
+    /// like any other complete mod.
+    ///
+    /// Everything the installer would always install goes into the root of the mod. The groups
+    /// of the steps that were shown become the mod's options, with the picks from the wizard
+    /// as their defaults, so they can be changed later without going through it again.
+    pub fn install(&self, output_dir: &Path) -> Result<Modification, String> {
+        fs::create_dir_all(output_dir)
+            .map_err(|err| format!("Failed to create {:?}: {}", output_dir, err))?;
+        let (always, groups) = self.plan();
+        let mut installed = self
+            .install_files(&always, output_dir, Path::new(""))?
+            .len();
+
+        let mut option_groups = Vec::new();
+        let mut names = HashSet::new();
+        let mut folders = HashSet::new();
+        for PlannedGroup {
+            kind,
+            group,
+            plugins,
+        } in groups
+        {
+            let mut options = Vec::new();
+            for (plugin, selected) in plugins {
+                // Options are referred to by name, which the installer doesn't keep unique
+                let mut name = plugin.name.clone();
+                if names.contains(&name) {
+                    name = format!("{} / {}", group.name, plugin.name);
+                }
+                let mut count = 1;
+                while names.contains(&name) {
+                    count += 1;
+                    name = format!("{} / {} {}", group.name, plugin.name, count);
+                }
+                names.insert(name.clone());
+                let folder = unique_folder(&name, &mut folders);
+                let mut files = Vec::new();
+                add_files(&plugin.files, &mut files);
+                files.sort_by_key(|(entry, _)| entry.priority);
+                let paks = self.install_files(&files, output_dir, &folder)?;
+                installed += paks.len();
+                options.push(ModOption {
+                    name,
+                    description: plugin.description.trim().to_string(),
+                    paks,
+                    default: selected,
+                    ..ModOption::default()
+                });
+            }
+            option_groups.push(OptionGroup {
+                name: group.name.clone(),
+                kind,
+                options,
+                ..OptionGroup::default()
+            });
+        }
+        if installed == 0 {
+            return Err(format!(
+                "{} has no paks to install, so there is nothing the game could load",
+                self.name()
+            ));
+        }
+
+        let mut modification = Modification::new();
+        modification.name = self.name().to_string();
+        modification.options = OptionGroups(option_groups);
+        modification.author = self.info.author.clone();
+        modification.version = self.info.version.clone();
+        modification.description = self.info.description.clone();
+        if let Some(image) = self
+            .config
+            .module_image
+            .as_ref()
+            .and_then(|image| self.resolve(&image.path))
+        {
+            let destination = Path::new("images").join(image.file_name().unwrap());
+            fs::create_dir_all(output_dir.join("images"))
+                .map_err(|err| format!("Failed to create images folder: {}", err))?;
+            if self.source.display_path(image) != output_dir.join(&destination) {
+                self.source.copy_to(image, &output_dir.join(&destination))?;
+            }
+            modification.images.push(destination);
+        }
+        let json = manifest::serialize(&modification, ManifestFormat::Json)
+            .map_err(|err| format!("Failed to write mod.json: {}", err))?;
+        fs::write(output_dir.join("mod.json"), json)
+            .map_err(|err| format!("Failed to write mod.json in {:?}: {}", output_dir, err))?;
+        schema::write_schema(output_dir, false)?;
+        Modification::build(output_dir.to_path_buf())
+    }
+}
+
+fn add_files(list: &FileList, files: &mut Vec<(FileEntry, bool)>) {
+    for item in &list.items {
+        match item {
+            FileItem::File(entry) => files.push((entry.clone(), false)),
+            FileItem::Folder(entry) => files.push((entry.clone(), true)),
+        }
+    }
+}
+
+// A folder under options/ for the files of an option, named after it where the name allows
+fn unique_folder(name: &str, taken: &mut HashSet<String>) -> PathBuf {
+    let base: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let base = base.trim().trim_end_matches('.').to_string();
+    let base = if base.is_empty() {
+        "option".to_string()
+    } else {
+        base
+    };
+    let mut folder = base.clone();
+    let mut count = 1;
+    while !taken.insert(folder.to_lowercase()) {
+        count += 1;
+        folder = format!("{} {}", base, count);
+    }
+    Path::new("options").join(folder)
+}
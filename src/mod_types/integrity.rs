@@ -0,0 +1,308 @@
+use crate::mod_types::ModSource;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityProblem {
+    Missing { path: String },
+    // The file is there but its contents changed, usually a truncated download or copy
+    Mismatch { path: String },
+    Unreadable { path: String, error: String },
+    // A pak or image the manifest has no hash for, which would be deployed without being checked
+    Unexpected { path: String },
+}
+
+impl Display for IntegrityProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityProblem::Missing { path } => write!(f, "{} is missing", path),
+            IntegrityProblem::Mismatch { path } => {
+                write!(f, "{} doesn't match its hash, it may be corrupted", path)
+            }
+            IntegrityProblem::Unreadable { path, error } => {
+                write!(f, "{} couldn't be checked: {}", path, error)
+            }
+            IntegrityProblem::Unexpected { path } => {
+                write!(f, "{} isn't listed in the manifest's hashes", path)
+            }
+        }
+    }
+}
+
+fn hash_reader(reader: &mut dyn Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    File::open(path)
+        .and_then(|mut file| hash_reader(&mut file))
+        .map_err(|err| format!("Failed to read {:?}: {}", path, err))
+}
+
+pub fn is_sha256(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// Hashes are keyed by the file's path relative to the mod root, with forward slashes
+fn hash_key(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+// Paks and images are hashed, the manifest itself can't contain its own hash
+fn is_hashed(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    matches!(extension.as_deref(), Some("pak" | "png" | "jpg" | "jpeg"))
+}
+
+/// Hashes every pak and image of a mod with SHA-256.
+pub fn hash_mod_files(source: &ModSource) -> Result<BTreeMap<String, String>, String> {
+    let mut hashes = BTreeMap::new();
+    for path in source.list_all(Path::new("")) {
+        if is_hashed(&path) {
+            let hash = source.with_reader(&path, hash_reader)?;
+            hashes.insert(hash_key(&path), hash);
+        }
+    }
+    Ok(hashes)
+}
+
+/// Checks the files of a mod against the hashes in its manifest. Once a manifest lists hashes,
+/// paks and images it doesn't list are reported too.
+pub fn verify_files(
+    source: &ModSource,
+    hashes: &BTreeMap<String, String>,
+) -> Vec<IntegrityProblem> {
+    let mut problems = Vec::new();
+    for (path, expected) in hashes {
+        let relative = Path::new(path);
+        if !source.exists(relative) {
+            problems.push(IntegrityProblem::Missing { path: path.clone() });
+            continue;
+        }
+        match source.with_reader(relative, hash_reader) {
+            Ok(hash) if hash.eq_ignore_ascii_case(expected) => {}
+            Ok(_) => problems.push(IntegrityProblem::Mismatch { path: path.clone() }),
+            Err(error) => problems.push(IntegrityProblem::Unreadable {
+                path: path.clone(),
+                error,
+            }),
+        }
+    }
+    if !hashes.is_empty() {
+        for path in source.list_all(Path::new("")) {
+            let key = hash_key(&path);
+            if is_hashed(&path) && !hashes.contains_key(&key) {
+                problems.push(IntegrityProblem::Unexpected { path: key });
+            }
+        }
+    }
+    problems
+}
+
+struct VerifyJob {
+    path: PathBuf,
+    source: ModSource,
+    hashes: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct VerifyOutcome {
+    // The mod's path, which is how it's found again once the check is done
+    pub path: PathBuf,
+    pub problems: Vec<IntegrityProblem>,
+}
+
+// Checks mods' files against their hashes on a background thread, hashing the paks of a big
+// mod list would freeze the window otherwise. Results are collected with poll(), which is
+// meant to be called once per frame.
+#[derive(Debug, Default)]
+pub struct FileVerifier {
+    jobs: Option<Sender<VerifyJob>>,
+    outcomes: Option<Receiver<VerifyOutcome>>,
+    pending: Vec<PathBuf>,
+    // Mods queued since the verifier was last idle, for showing progress
+    queued: usize,
+}
+
+impl FileVerifier {
+    fn start(&mut self) -> &Sender<VerifyJob> {
+        if self.jobs.is_none() {
+            let (job_sender, job_receiver) = channel::<VerifyJob>();
+            let (outcome_sender, outcome_receiver) = channel();
+            // One thread is enough, reading the files is what takes the time
+            thread::spawn(move || {
+                for job in job_receiver {
+                    let problems = verify_files(&job.source, &job.hashes);
+                    let outcome = VerifyOutcome {
+                        path: job.path,
+                        problems,
+                    };
+                    if outcome_sender.send(outcome).is_err() {
+                        break;
+                    }
+                }
+            });
+            self.jobs = Some(job_sender);
+            self.outcomes = Some(outcome_receiver);
+        }
+        self.jobs.as_ref().unwrap()
+    }
+    pub fn submit(&mut self, path: PathBuf, source: ModSource, hashes: BTreeMap<String, String>) {
+        if self.pending.is_empty() {
+            self.queued = 0;
+        }
+        self.pending.push(path.clone());
+        self.queued += 1;
+        let job = VerifyJob {
+            path,
+            source,
+            hashes,
+        };
+        if self.start().send(job).is_err() {
+            eprintln!("Failed to queue a mod for verification");
+        }
+    }
+    pub fn is_pending(&self, path: &Path) -> bool {
+        self.pending.iter().any(|pending| pending == path)
+    }
+    // How many of the queued mods are done and how many were queued, None when idle
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            Some((self.queued - self.pending.len(), self.queued))
+        }
+    }
+    pub fn poll(&mut self) -> Vec<VerifyOutcome> {
+        let Some(outcomes) = &self.outcomes else {
+            return Vec::new();
+        };
+        let finished: Vec<VerifyOutcome> = outcomes.try_iter().collect();
+        for outcome in &finished {
+            if let Some(index) = self.pending.iter().position(|path| *path == outcome.path) {
+                self.pending.remove(index);
+            }
+        }
+        finished
+    }
+}
+
+// What checking the paks copied into ~mods found
+#[derive(Debug, Default, Clone)]
+pub struct DeploymentReport {
+    pub verified: usize,
+    // Files that differed from their source and were copied again
+    pub repaired: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl DeploymentReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!("Deployed files verified: {}", self.verified);
+        if !self.repaired.is_empty() {
+            summary.push_str(&format!(", repaired {}", self.repaired.join(", ")));
+        }
+        if !self.failed.is_empty() {
+            summary.push_str(&format!(", couldn't repair {}", self.failed.join(", ")));
+        }
+        summary
+    }
+}
+
+fn matches_source(source: &ModSource, relative: &Path, destination: &Path) -> Result<bool, String> {
+    let expected = source.with_reader(relative, hash_reader)?;
+    let deployed = hash_file(destination)?;
+    Ok(expected == deployed)
+}
+
+/// Copies a file of a mod to `destination`. With `verify` the copy is hashed against the
+/// original and copied again once if they differ, noting the outcome in `report`.
+pub fn deploy_file(
+    source: &ModSource,
+    relative: &Path,
+    destination: &Path,
+    verify: bool,
+    report: &mut DeploymentReport,
+) -> Result<(), String> {
+    source.copy_to(relative, destination)?;
+    if !verify {
+        return Ok(());
+    }
+    let name = destination
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match matches_source(source, relative, destination) {
+        Ok(true) => report.verified += 1,
+        Ok(false) => {
+            source.copy_to(relative, destination)?;
+            match matches_source(source, relative, destination) {
+                Ok(true) => {
+                    report.verified += 1;
+                    report.repaired.push(name);
+                }
+                Ok(false) => report.failed.push(name),
+                Err(err) => report.failed.push(format!("{} ({})", name, err)),
+            }
+        }
+        Err(err) => report.failed.push(format!("{} ({})", name, err)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+    use std::fs;
+
+    #[test]
+    fn verify_files_finds_missing_changed_and_unlisted_files() {
+        let dir = test_dir("verify_files");
+        fs::create_dir_all(dir.join("images")).unwrap();
+        fs::write(dir.join("Suit_P.pak"), "suit").unwrap();
+        fs::write(dir.join("images/cover.png"), "cover").unwrap();
+        let source = ModSource::Directory(dir.clone());
+        let hashes = hash_mod_files(&source).unwrap();
+        assert_eq!(
+            hashes.keys().collect::<Vec<_>>(),
+            ["Suit_P.pak", "images/cover.png"]
+        );
+        assert!(verify_files(&source, &hashes).is_empty());
+
+        fs::write(dir.join("Suit_P.pak"), "suit, but truncated").unwrap();
+        fs::remove_file(dir.join("images/cover.png")).unwrap();
+        fs::write(dir.join("Extra_P.pak"), "extra").unwrap();
+        fs::write(dir.join("readme.txt"), "not hashed").unwrap();
+        let mut problems = verify_files(&source, &hashes);
+        problems.sort_by_key(ToString::to_string);
+        assert_eq!(
+            problems,
+            [
+                IntegrityProblem::Unexpected {
+                    path: "Extra_P.pak".to_string()
+                },
+                IntegrityProblem::Mismatch {
+                    path: "Suit_P.pak".to_string()
+                },
+                IntegrityProblem::Missing {
+                    path: "images/cover.png".to_string()
+                },
+            ]
+        );
+
+        // Mods without hashes have nothing to check against
+        assert!(verify_files(&source, &BTreeMap::new()).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,12 @@
+use crate::mod_types::ModSource;
 use std::path::PathBuf;
 
 #[derive(Debug, Default)]
 pub struct LoosePak {
     pub name: String,
     pub path: PathBuf,
+    pub source: ModSource,
+    pub entry: PathBuf,
     pub enabled: bool,
 }
 impl LoosePak {
@@ -11,9 +14,16 @@ impl LoosePak {
         Self::default()
     }
     pub fn build(path: PathBuf) -> Result<Self, String> {
+        let parent = path.parent().ok_or("Pak has no parent directory")?;
+        let entry = PathBuf::from(path.file_name().ok_or("Pak has no file name")?);
+        Self::from_source(ModSource::Directory(parent.to_path_buf()), entry)
+    }
+    pub fn from_source(source: ModSource, entry: PathBuf) -> Result<Self, String> {
         let mut pak = Self::new();
-        pak.name = path.file_name().unwrap().to_str().unwrap().to_string();
-        pak.path = path;
+        pak.name = entry.file_name().unwrap().to_str().unwrap().to_string();
+        pak.path = source.display_path(&entry);
+        pak.source = source;
+        pak.entry = entry;
         pak.enabled = false;
         return Ok(pak);
     }
@@ -0,0 +1,245 @@
+use crate::mod_types::schema::SCHEMA_FILE;
+use crate::mod_types::ModSource;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The mod.json format this manager writes. Manifests without a `format_version` are version 1.
+///
+/// Manifest structs are `#[serde(default)]` so manifests written by older or newer tools still
+/// load, and keep the keys they don't know in a `#[serde(flatten)] extras` map so saving a
+/// manifest doesn't lose them.
+pub const FORMAT_VERSION: u64 = 2;
+
+// Each migration upgrades a manifest by one version, starting from version 1
+const MIGRATIONS: &[fn(&mut Value)] = &[pak_images_from_root];
+
+// Version 1 listed MultiPak images relative to the "images" folder, while complete mods
+// listed them relative to the mod root. Version 2 uses the mod root for both.
+fn pak_images_from_root(manifest: &mut Value) {
+    let Some(paks) = manifest.get_mut("paks").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for pak in paks {
+        let Some(images) = pak.get_mut("images").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for image in images {
+            if let Some(path) = image.as_str() {
+                *image = Value::String(format!("images/{}", path));
+            }
+        }
+    }
+}
+
+pub fn format_version(manifest: &Value) -> Result<u64, String> {
+    match manifest.get("format_version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| format!("format_version {} is not a valid version", version)),
+    }
+}
+
+/// Upgrades a manifest to the current format in place. Returns the version it started at.
+pub fn migrate(manifest: &mut Value) -> Result<u64, String> {
+    let version = format_version(manifest)?;
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "mod.json uses format version {}, but this manager only understands up to {}. Try updating the manager.",
+            version, FORMAT_VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(manifest);
+    }
+    if let Some(object) = manifest.as_object_mut() {
+        object.insert("format_version".to_string(), FORMAT_VERSION.into());
+    }
+    Ok(version)
+}
+
+/// The file formats a manifest can be written in. A mod has one of mod.json, mod.toml or
+/// mod.yaml, looked for in that order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ManifestFormat {
+    pub const ALL: [ManifestFormat; 3] = [Self::Json, Self::Toml, Self::Yaml];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "mod.json",
+            Self::Toml => "mod.toml",
+            Self::Yaml => "mod.yaml",
+        }
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+        }
+    }
+    fn parse(self, content: &str) -> Result<Value, String> {
+        match self {
+            Self::Json => serde_json::from_str(content).map_err(|err| err.to_string()),
+            Self::Toml => toml::from_str(content).map_err(|err| err.to_string()),
+            Self::Yaml => serde_yaml::from_str(content).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Finds which manifest a mod has, if any.
+pub fn find(source: &ModSource) -> Option<ManifestFormat> {
+    ManifestFormat::ALL
+        .into_iter()
+        .find(|format| source.exists(Path::new(format.file_name())))
+}
+
+pub fn is_manifest(file_name: &str) -> bool {
+    ManifestFormat::ALL
+        .iter()
+        .any(|format| format.file_name() == file_name)
+}
+
+// Where the manifest of a mod is, for messages about it
+pub fn manifest_path(source: &ModSource) -> PathBuf {
+    let format = find(source).unwrap_or_default();
+    source.display_path(Path::new(format.file_name()))
+}
+
+/// Reads the manifest of a mod and upgrades it to the current format, leaving out the
+/// bookkeeping keys that `serialize` adds.
+pub fn read_manifest(source: &ModSource) -> Result<Value, String> {
+    let format = find(source).ok_or_else(|| {
+        format!(
+            "No mod.json, mod.toml or mod.yaml found in {:?}",
+            source.path()
+        )
+    })?;
+    let manifest_path = source.display_path(Path::new(format.file_name()));
+    let content = source.read_to_string(Path::new(format.file_name()))?;
+    let mut manifest = format.parse(&content).map_err(|err| {
+        format!(
+            "Failed to parse {} from file at {:?}: {}",
+            format.label(),
+            manifest_path,
+            err
+        )
+    })?;
+    migrate(&mut manifest).map_err(|err| format!("{:?}: {}", manifest_path, err))?;
+    // These are written back by serialize, so they shouldn't end up among a mod's unknown keys
+    if let Some(object) = manifest.as_object_mut() {
+        object.shift_remove("$schema");
+        object.shift_remove("format_version");
+    }
+    Ok(manifest)
+}
+
+/// Serializes a manifest, stamping it with the current format version and its schema.
+pub fn serialize<T: Serialize>(manifest: &T, format: ManifestFormat) -> Result<String, String> {
+    let value = serde_json::to_value(manifest).map_err(|err| err.to_string())?;
+    // Keep the version at the top where people editing the file will see it
+    let mut object = Map::new();
+    object.insert("format_version".to_string(), FORMAT_VERSION.into());
+    if let Value::Object(fields) = value {
+        object.extend(fields);
+    }
+    // Point editors at the schema the rest can be checked against. TOML and YAML have no
+    // $schema key, but their language servers read these comments instead.
+    match format {
+        ManifestFormat::Json => {
+            let mut with_schema = Map::new();
+            with_schema.insert("$schema".to_string(), SCHEMA_FILE.into());
+            with_schema.extend(object);
+            serde_json::to_string_pretty(&with_schema).map_err(|err| err.to_string())
+        }
+        ManifestFormat::Toml => toml::to_string_pretty(&object)
+            .map(|body| format!("#:schema {}\n\n{}", SCHEMA_FILE, body))
+            .map_err(|err| err.to_string()),
+        ManifestFormat::Yaml => serde_yaml::to_string(&object)
+            .map(|body| format!("# yaml-language-server: $schema={}\n{}", SCHEMA_FILE, body))
+            .map_err(|err| err.to_string()),
+    }
+}
+
+/// Rewrites an outdated mod.json in `mod_dir` in the current format, keeping the original as
+/// mod.json.v{version}.bak. Returns the version it was upgraded from, if it needed upgrading.
+/// TOML and YAML manifests are only upgraded when read, rewriting them would drop their comments.
+pub fn upgrade_on_disk(mod_dir: &Path) -> Result<Option<u64>, String> {
+    let json_path = mod_dir.join("mod.json");
+    let json_content = fs::read_to_string(&json_path)
+        .map_err(|err| format!("Failed to read {:?}: {}", json_path, err))?;
+    let mut manifest: Value = serde_json::from_str(&json_content)
+        .map_err(|err| format!("Failed to parse JSON from file at {:?}: {}", json_path, err))?;
+    let version = migrate(&mut manifest).map_err(|err| format!("{:?}: {}", json_path, err))?;
+    if version == FORMAT_VERSION {
+        return Ok(None);
+    }
+
+    let backup_path = mod_dir.join(format!("mod.json.v{}.bak", version));
+    fs::copy(&json_path, &backup_path)
+        .map_err(|err| format!("Failed to back up {:?}: {}", json_path, err))?;
+    let upgraded = serde_json::to_string_pretty(&manifest).map_err(|err| err.to_string())?;
+    fs::write(&json_path, upgraded)
+        .map_err(|err| format!("Failed to write {:?}: {}", json_path, err))?;
+    Ok(Some(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mod_types::Modification;
+    use crate::test_dir;
+    use serde_json::json;
+
+    #[test]
+    fn migrate_moves_multi_pak_images_under_the_images_folder() {
+        let mut manifest = json!({
+            "name": "Suits",
+            "paks": [{ "pak": "Suit_P.pak", "images": ["suit.png"] }, { "pak": "Other_P.pak" }]
+        });
+        assert_eq!(migrate(&mut manifest), Ok(1));
+        assert_eq!(manifest["paks"][0]["images"], json!(["images/suit.png"]));
+        assert_eq!(manifest["format_version"], json!(FORMAT_VERSION));
+
+        // Already current, nothing to do
+        let mut current = manifest.clone();
+        assert_eq!(migrate(&mut current), Ok(FORMAT_VERSION));
+        assert_eq!(current, manifest);
+    }
+
+    #[test]
+    fn migrate_refuses_unknown_versions() {
+        assert!(migrate(&mut json!({ "format_version": FORMAT_VERSION + 1 })).is_err());
+        assert!(migrate(&mut json!({ "format_version": 0 })).is_err());
+        assert!(migrate(&mut json!({ "format_version": "2" })).is_err());
+    }
+
+    #[test]
+    fn unknown_keys_survive_every_format() {
+        let mut modification = Modification::new();
+        modification.name = "Suit".to_string();
+        modification
+            .extras
+            .insert("x_nexus".to_string(), json!({ "id": 42, "tags": ["suit"] }));
+        for format in ManifestFormat::ALL {
+            let dir = test_dir(&format!("round_trip_{}", format.label()));
+            let content = serialize(&modification, format).unwrap();
+            fs::write(dir.join(format.file_name()), content).unwrap();
+            let manifest = read_manifest(&ModSource::Directory(dir.clone())).unwrap();
+            let read: Modification = serde_json::from_value(manifest).unwrap();
+            assert_eq!(read.name, "Suit", "{}", format.label());
+            assert_eq!(read.extras, modification.extras, "{}", format.label());
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::PathBuf;
+
+// What others may do with the mod's files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Redistribution {
+    #[default]
+    Unspecified,
+    Allowed,
+    WithCredit,
+    AskFirst,
+    NotAllowed,
+}
+
+impl Redistribution {
+    pub const ALL: [Redistribution; 5] = [
+        Self::Unspecified,
+        Self::Allowed,
+        Self::WithCredit,
+        Self::AskFirst,
+        Self::NotAllowed,
+    ];
+
+    pub fn is_unspecified(&self) -> bool {
+        *self == Self::Unspecified
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unspecified => "Not specified",
+            Self::Allowed => "Allowed",
+            Self::WithCredit => "Allowed with credit",
+            Self::AskFirst => "Ask the author first",
+            Self::NotAllowed => "Not allowed",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Contributor {
+    pub name: String,
+    /// What they worked on, like "textures" or "testing"
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub role: String,
+}
+
+// Details about a mod that don't change how it's loaded. Flattened into the manifest, so these
+// are top level keys of mod.json.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub homepage: String,
+    /// Where the files the mod was made from can be found
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub repository: String,
+    /// An SPDX identifier like "CC-BY-4.0", or the license in a few words
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub license: String,
+    #[serde(skip_serializing_if = "Redistribution::is_unspecified")]
+    pub redistribution: Redistribution,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    /// The image shown first for the mod, relative to its root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<PathBuf>,
+    /// When the mod was last updated, as YYYY-MM-DD
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub updated: String,
+}
+
+impl Metadata {
+    // Whether `updated` is empty or a plausible YYYY-MM-DD date
+    pub fn has_valid_date(&self) -> bool {
+        if self.updated.is_empty() {
+            return true;
+        }
+        let parts: Vec<&str> = self.updated.split('-').collect();
+        let number = |part: &str, digits: usize| {
+            Some(part)
+                .filter(|part| part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|part| part.parse::<u32>().ok())
+        };
+        match parts[..] {
+            [year, month, day] => {
+                number(year, 4).is_some()
+                    && number(month, 2).is_some_and(|month| (1..=12).contains(&month))
+                    && number(day, 2).is_some_and(|day| (1..=31).contains(&day))
+            }
+            _ => false,
+        }
+    }
+    pub fn links(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("Homepage", self.homepage.as_str()),
+            ("Source", self.repository.as_str()),
+        ]
+        .into_iter()
+        .filter(|(_, url)| !url.is_empty())
+    }
+}
@@ -1,11 +1,38 @@
+mod compatibility;
+mod dependencies;
+mod deployment;
+mod fomod;
+mod integrity;
 mod loose_pak;
+pub mod manifest;
+mod metadata;
+mod mod_source;
 mod modification;
 pub(crate) mod multi_pak;
+mod option_group;
+mod resolver;
+pub mod schema;
+mod signature;
+mod translations;
 
+pub use compatibility::*;
+pub use dependencies::*;
+pub use deployment::*;
+pub use fomod::*;
+pub use integrity::*;
 pub use loose_pak::*;
+pub use metadata::*;
+pub use mod_source::*;
 pub use modification::*;
 pub use multi_pak::*;
+pub use option_group::*;
+pub use resolver::*;
+use schema::ManifestError;
+pub use signature::*;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::path::PathBuf;
+pub use translations::*;
 
 pub enum ModType {
     Complete(Modification),
@@ -23,3 +50,122 @@ impl Debug for ModType {
         }
     }
 }
+
+impl ModType {
+    // The folder, archive or pak file the mod was built from
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.file_path,
+            ModType::LoosePak(mod_type) => mod_type.path(),
+            ModType::MultiPak(mod_type) => &mod_type.path,
+        }
+    }
+    pub fn name(&self) -> &str {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.name,
+            ModType::LoosePak(mod_type) => &mod_type.name,
+            ModType::MultiPak(mod_type) => &mod_type.name,
+        }
+    }
+    // Loose paks have no manifest, so they can't be depended on
+    pub fn id(&self) -> Option<&str> {
+        let id = match self {
+            ModType::Complete(mod_type) => &mod_type.id,
+            ModType::LoosePak(_) => return None,
+            ModType::MultiPak(mod_type) => &mod_type.id,
+        };
+        Some(id.as_str()).filter(|id| !id.is_empty())
+    }
+    pub fn requires(&self) -> &[String] {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.requires,
+            ModType::LoosePak(_) => &[],
+            ModType::MultiPak(mod_type) => &mod_type.requires,
+        }
+    }
+    pub fn conflicts_with(&self) -> &[String] {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.conflicts_with,
+            ModType::LoosePak(_) => &[],
+            ModType::MultiPak(mod_type) => &mod_type.conflicts_with,
+        }
+    }
+    pub fn exclusive(&self) -> &[String] {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.exclusive,
+            ModType::LoosePak(_) => &[],
+            ModType::MultiPak(mod_type) => &mod_type.exclusive,
+        }
+    }
+    // Loose paks have no manifest to declare it in
+    pub fn compatibility(&self) -> Option<&Compatibility> {
+        match self {
+            ModType::Complete(mod_type) => Some(&mod_type.compatibility),
+            ModType::LoosePak(_) => None,
+            ModType::MultiPak(mod_type) => Some(&mod_type.compatibility),
+        }
+    }
+    // What checking the mod's files against its manifest needs, loose paks have no manifest
+    pub fn hashes(&self) -> Option<(&ModSource, &BTreeMap<String, String>)> {
+        match self {
+            ModType::Complete(mod_type) => Some((&mod_type.source, &mod_type.hashes)),
+            ModType::LoosePak(_) => None,
+            ModType::MultiPak(mod_type) => Some((&mod_type.source, &mod_type.hashes)),
+        }
+    }
+    pub fn set_integrity_problems(&mut self, problems: Vec<IntegrityProblem>) {
+        match self {
+            ModType::Complete(mod_type) => mod_type.integrity_problems = problems,
+            ModType::LoosePak(_) => {}
+            ModType::MultiPak(mod_type) => mod_type.integrity_problems = problems,
+        }
+    }
+    pub fn is_signed(&self) -> bool {
+        match self {
+            ModType::Complete(mod_type) => mod_type.signature != SignatureCheck::Unsigned,
+            ModType::LoosePak(_) => false,
+            ModType::MultiPak(mod_type) => mod_type.signature != SignatureCheck::Unsigned,
+        }
+    }
+    pub fn warnings(&self) -> &[ManifestError] {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.warnings,
+            ModType::LoosePak(_) => &[],
+            ModType::MultiPak(mod_type) => &mod_type.warnings,
+        }
+    }
+    pub fn integrity_problems(&self) -> &[IntegrityProblem] {
+        match self {
+            ModType::Complete(mod_type) => &mod_type.integrity_problems,
+            ModType::LoosePak(_) => &[],
+            ModType::MultiPak(mod_type) => &mod_type.integrity_problems,
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            ModType::Complete(mod_type) => mod_type.enabled,
+            ModType::LoosePak(mod_type) => mod_type.enabled,
+            ModType::MultiPak(mod_type) => mod_type.enabled,
+        }
+    }
+    // Carries the user's choices over from the mod this one was built again from
+    pub fn keep_state_from(&mut self, previous: &ModType) {
+        self.set_enabled(previous.is_enabled());
+        match (self, previous) {
+            (ModType::Complete(mod_type), ModType::Complete(previous)) => {
+                mod_type.options.keep_selection(&previous.options)
+            }
+            (ModType::MultiPak(mod_type), ModType::MultiPak(previous)) => {
+                mod_type.keep_selection(previous)
+            }
+            _ => {}
+        }
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        match self {
+            ModType::Complete(mod_type) => mod_type.enabled = enabled,
+            ModType::LoosePak(mod_type) => mod_type.enabled = enabled,
+            ModType::MultiPak(mod_type) => mod_type.enabled = enabled,
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use crate::archive;
+use crate::mod_types::{is_fomod_config, manifest};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// Where the files of a mod live. Archive sources are read in place, so nothing has to be
+// extracted until a pak is deployed.
+#[derive(Debug, Clone)]
+pub enum ModSource {
+    Directory(PathBuf),
+    Archive {
+        path: PathBuf,
+        root: String,
+        entries: Vec<String>,
+    },
+}
+
+impl Default for ModSource {
+    fn default() -> Self {
+        ModSource::Directory(PathBuf::new())
+    }
+}
+
+impl ModSource {
+    pub fn open_archive(path: PathBuf) -> Result<Self, String> {
+        let entries = archive::list_entries(&path)
+            .map_err(|err| format!("Failed to read archive {:?}: {}", path, err))?;
+
+        // Mods are usually packed inside a folder, so the root is wherever the manifest
+        // (or failing that, a FOMOD installer or the first pak) sits closest to the top
+        let depth = |entry: &&String| entry.matches('/').count();
+        let parent_of = |entry: &str| match entry.rfind('/') {
+            Some(index) => entry[..=index].to_string(),
+            None => String::new(),
+        };
+        let root = entries
+            .iter()
+            .filter(|entry| entry.rsplit('/').next().is_some_and(manifest::is_manifest))
+            .min_by_key(depth)
+            .map(|entry| parent_of(entry))
+            .or_else(|| {
+                // The installer sits in a "fomod" folder next to the files it installs
+                entries
+                    .iter()
+                    .filter(|entry| is_fomod_config(entry))
+                    .min_by_key(depth)
+                    .map(|entry| parent_of(parent_of(entry).trim_end_matches('/')))
+            })
+            .or_else(|| {
+                entries
+                    .iter()
+                    .filter(|entry| entry.ends_with(".pak"))
+                    .min_by_key(depth)
+                    .map(|entry| parent_of(entry))
+            })
+            .ok_or_else(|| format!("No mod found in archive {:?}", path))?;
+
+        Ok(ModSource::Archive {
+            path,
+            root,
+            entries,
+        })
+    }
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            ModSource::Directory(path) => path,
+            ModSource::Archive { path, .. } => path,
+        }
+    }
+    // What to call a mod that doesn't name itself, the folder or archive without its extension
+    pub fn name(&self) -> String {
+        let path = self.path();
+        let name = match self {
+            ModSource::Directory(_) => path.file_name(),
+            ModSource::Archive { .. } => path.file_stem(),
+        };
+        name.unwrap_or_default().to_string_lossy().into_owned()
+    }
+    pub fn is_archive(&self) -> bool {
+        matches!(self, ModSource::Archive { .. })
+    }
+    fn entry_name(root: &str, relative: &Path) -> String {
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        format!("{}{}", root, relative.trim_start_matches('/'))
+    }
+    // Path used for display and texture ids. For archives this does not exist on disk.
+    pub fn display_path(&self, relative: &Path) -> PathBuf {
+        match self {
+            ModSource::Directory(path) => path.join(relative),
+            ModSource::Archive { path, root, .. } => path.join(Self::entry_name(root, relative)),
+        }
+    }
+    pub fn exists(&self, relative: &Path) -> bool {
+        match self {
+            ModSource::Directory(path) => path.join(relative).exists(),
+            ModSource::Archive { root, entries, .. } => {
+                let name = Self::entry_name(root, relative);
+                let dir_prefix = format!("{}/", name);
+                entries
+                    .iter()
+                    .any(|entry| *entry == name || entry.starts_with(&dir_prefix))
+            }
+        }
+    }
+    pub fn is_dir(&self, relative: &Path) -> bool {
+        match self {
+            ModSource::Directory(path) => path.join(relative).is_dir(),
+            ModSource::Archive { root, entries, .. } => {
+                let dir_prefix = format!("{}/", Self::entry_name(root, relative));
+                entries.iter().any(|entry| entry.starts_with(&dir_prefix))
+            }
+        }
+    }
+    fn dir_prefix(root: &str, relative: &Path) -> String {
+        let prefix = Self::entry_name(root, relative);
+        if prefix.is_empty() || prefix.ends_with('/') {
+            prefix
+        } else {
+            format!("{}/", prefix)
+        }
+    }
+    // Lists the files directly inside `relative`, returned relative to the mod root
+    pub fn list(&self, relative: &Path) -> Vec<PathBuf> {
+        match self {
+            ModSource::Directory(path) => match path.join(relative).read_dir() {
+                Ok(entries) => entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_file())
+                    .map(|entry| relative.join(entry.file_name()))
+                    .collect(),
+                Err(err) => {
+                    eprintln!(
+                        "Failed to read directory {:?}: {}",
+                        path.join(relative),
+                        err
+                    );
+                    Vec::new()
+                }
+            },
+            ModSource::Archive { root, entries, .. } => {
+                let prefix = Self::dir_prefix(root, relative);
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.strip_prefix(&prefix))
+                    .filter(|name| !name.is_empty() && !name.contains('/'))
+                    .map(|name| relative.join(name))
+                    .collect()
+            }
+        }
+    }
+    // Lists every file below `relative`, including those in subfolders
+    pub fn list_all(&self, relative: &Path) -> Vec<PathBuf> {
+        match self {
+            ModSource::Directory(path) => {
+                let mut files = Vec::new();
+                let mut dirs = vec![relative.to_path_buf()];
+                while let Some(dir) = dirs.pop() {
+                    match path.join(&dir).read_dir() {
+                        Ok(entries) => {
+                            for entry in entries.filter_map(|entry| entry.ok()) {
+                                if entry.path().is_dir() {
+                                    dirs.push(dir.join(entry.file_name()));
+                                } else {
+                                    files.push(dir.join(entry.file_name()));
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            eprintln!("Failed to read directory {:?}: {}", path.join(&dir), err)
+                        }
+                    }
+                }
+                files
+            }
+            ModSource::Archive { root, entries, .. } => {
+                let prefix = Self::dir_prefix(root, relative);
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.strip_prefix(&prefix))
+                    .filter(|name| !name.is_empty())
+                    .map(|name| relative.join(name))
+                    .collect()
+            }
+        }
+    }
+    pub fn read_bytes(&self, relative: &Path) -> Result<Vec<u8>, String> {
+        match self {
+            ModSource::Directory(path) => std::fs::read(path.join(relative))
+                .map_err(|err| format!("Failed to read {:?}: {}", path.join(relative), err)),
+            ModSource::Archive { path, root, .. } => {
+                archive::read_entry(path, &Self::entry_name(root, relative)).map_err(|err| {
+                    format!("Failed to read {:?}: {}", self.display_path(relative), err)
+                })
+            }
+        }
+    }
+    // Hands a reader over a single file of the mod to `read`, without loading it all into memory
+    pub fn with_reader<T>(
+        &self,
+        relative: &Path,
+        read: impl FnOnce(&mut dyn Read) -> io::Result<T>,
+    ) -> Result<T, String> {
+        let result = match self {
+            ModSource::Directory(path) => File::open(path.join(relative))
+                .and_then(|mut file| read(&mut file))
+                .map_err(|err| err.to_string()),
+            ModSource::Archive { path, root, .. } => {
+                archive::with_entry(path, &Self::entry_name(root, relative), read)
+                    .map_err(|err| err.to_string())
+            }
+        };
+        result.map_err(|err| format!("Failed to read {:?}: {}", self.display_path(relative), err))
+    }
+    pub fn read_to_string(&self, relative: &Path) -> Result<String, String> {
+        let bytes = self.read_bytes(relative)?;
+        String::from_utf8(bytes).map_err(|err| {
+            format!(
+                "{:?} is not valid UTF-8: {}",
+                self.display_path(relative),
+                err
+            )
+        })
+    }
+    // How big a file of the mod is once copied out, as the archive headers declare it
+    pub fn size(&self, relative: &Path) -> Result<u64, String> {
+        let result = match self {
+            ModSource::Directory(path) => std::fs::metadata(path.join(relative))
+                .map(|metadata| metadata.len())
+                .map_err(|err| err.to_string()),
+            ModSource::Archive { path, root, .. } => {
+                let name = Self::entry_name(root, relative);
+                archive::list_entry_info(path, None)
+                    .map_err(|err| err.to_string())
+                    .and_then(|entries| {
+                        entries
+                            .into_iter()
+                            .find(|entry| entry.name == name)
+                            .map(|entry| entry.size)
+                            .ok_or_else(|| "No such entry".to_string())
+                    })
+            }
+        };
+        result.map_err(|err| format!("Failed to read {:?}: {}", self.display_path(relative), err))
+    }
+    // Copies a single file of the mod to `destination`, streaming it out of the archive if needed
+    pub fn copy_to(&self, relative: &Path, destination: &Path) -> Result<(), String> {
+        let result = match self {
+            ModSource::Directory(path) => std::fs::copy(path.join(relative), destination)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            ModSource::Archive { path, root, .. } => {
+                archive::copy_entry(path, &Self::entry_name(root, relative), destination)
+                    .map(|_| ())
+                    .map_err(|err| err.to_string())
+            }
+        };
+        result.map_err(|err| format!("Failed to copy {:?}: {}", self.display_path(relative), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    fn archive_root(name: &str, entries: &[&str]) -> Result<String, String> {
+        let dir = test_dir(name);
+        let path = dir.join("Suit.zip");
+        let mut writer = ZipWriter::new(File::create(&path).unwrap());
+        for entry in entries {
+            writer
+                .start_file(*entry, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"x").unwrap();
+        }
+        writer.finish().unwrap();
+        let source = ModSource::open_archive(path);
+        std::fs::remove_dir_all(dir).unwrap();
+        match source? {
+            ModSource::Archive { root, .. } => Ok(root),
+            ModSource::Directory(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn archive_root_is_the_shallowest_manifest() {
+        let root = archive_root(
+            "root_manifest",
+            &[
+                "Suit/Extras/mod.json",
+                "Suit/mod.toml",
+                "Suit/Suit_P.pak",
+                "Top_P.pak",
+            ],
+        );
+        assert_eq!(root.unwrap(), "Suit/");
+    }
+
+    #[test]
+    fn archive_root_falls_back_to_installer_then_pak() {
+        let root = archive_root(
+            "root_fomod",
+            &["Suit/FOMOD/ModuleConfig.xml", "Suit/Body/Slim_P.pak"],
+        );
+        assert_eq!(root.unwrap(), "Suit/");
+        let root = archive_root("root_pak", &["Suit/Body/Slim_P.pak", "Suit/Suit_P.pak"]);
+        assert_eq!(root.unwrap(), "Suit/");
+        let root = archive_root("root_top", &["Suit_P.pak"]);
+        assert_eq!(root.unwrap(), "");
+        assert!(archive_root("root_none", &["readme.txt"]).is_err());
+    }
+}
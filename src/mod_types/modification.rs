@@ -1,60 +1,131 @@
-use serde_json;
-use std::path::PathBuf;
+use crate::mod_types::{
+    check_signature, manifest, schema, schema::ManifestError, Compatibility, IntegrityProblem,
+    Metadata, ModSource, OptionGroups, SignatureCheck, Translations,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize, schemars::JsonSchema)]
+#[serde(default)]
 pub struct Modification {
     pub name: String,
     pub description: String,
     pub author: String,
     pub version: String,
     pub images: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Translations::is_empty")]
+    pub translations: Translations,
+    /// Lets other mods refer to this one in requires and conflicts_with
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<String>,
+    /// Slots like a hero's skin that only one enabled mod can fill, e.g. "spider-man/suit"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclusive: Vec<String>,
+    /// Choices installed on top of the paks in the root of the mod
+    #[serde(default, skip_serializing_if = "OptionGroups::is_empty")]
+    pub options: OptionGroups,
+    /// SHA-256 of every pak and image, keyed by their path relative to the root of the mod
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    #[serde(flatten)]
+    pub compatibility: Compatibility,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
     #[serde(skip)]
     pub file_path: PathBuf,
     #[serde(skip)]
+    pub source: ModSource,
+    #[serde(skip)]
     pub enabled: bool,
+    // Files that didn't match their hash the last time they were checked
+    #[serde(skip)]
+    pub integrity_problems: Vec<IntegrityProblem>,
+    // Whether mod.sig holds up, who the key belongs to is up to the manager's keyring
+    #[serde(skip)]
+    pub signature: SignatureCheck,
+    // Problems with the manifest that don't stop the mod from loading, like a missing image
+    #[serde(skip)]
+    pub warnings: Vec<ManifestError>,
 }
 impl Modification {
     pub fn new() -> Self {
         Self::default()
     }
+    pub fn localized_name(&self, locale: &str) -> &str {
+        self.translations.name(locale, &self.name)
+    }
+    pub fn localized_description(&self, locale: &str) -> &str {
+        self.translations.description(locale, &self.description)
+    }
     pub fn pak_path(&self) -> PathBuf {
-        let entries = self.file_path.read_dir().unwrap();
-        for entry in entries {
-            let entry = entry.unwrap();
-            let path = entry.path();
+        self.source.display_path(&self.pak_entry())
+    }
+    // The pak's path relative to the root of the mod
+    pub fn pak_entry(&self) -> PathBuf {
+        for path in self.source.list(Path::new("")) {
             if path.to_str().unwrap().ends_with(".pak") {
                 return path;
             }
         }
         panic!("No pak file found in {:?}", self.file_path);
     }
+    // Every pak in the root of the mod, FOMOD installs can end up with more than one
+    pub fn pak_entries(&self) -> Vec<PathBuf> {
+        self.source
+            .list(Path::new(""))
+            .into_iter()
+            .filter(|path| path.to_str().unwrap().ends_with(".pak"))
+            .collect()
+    }
+    // The root paks plus those of the selected options
+    pub fn chosen_paks(&self) -> Vec<PathBuf> {
+        let mut paks = self.pak_entries();
+        paks.extend(self.options.chosen_paks().into_iter().cloned());
+        paks
+    }
     pub fn build(file_path: PathBuf) -> Result<Self, String> {
-        let json_path: PathBuf = file_path.join("mod.json");
+        Self::from_source(ModSource::Directory(file_path))
+    }
+    pub fn from_source(source: ModSource) -> Result<Self, String> {
+        let json_path: PathBuf = manifest::manifest_path(&source);
 
-        // Attempt to read the file; handle errors
-        let json_content = match std::fs::read_to_string(&json_path) {
-            Ok(content) => content,
-            Err(err) => {
-                return Err(format!(
-                    "Failed to read JSON file at {:?}: {}",
-                    json_path, err
-                ))
-            }
-        };
+        // Read the manifest, upgrading it if it was written for an older format
+        let manifest = manifest::read_manifest(&source)?;
+        let warnings = schema::validate_modification(&source, &manifest)
+            .map_err(|errors| format!("Invalid manifest at {:?}:{}", json_path, errors))?;
 
-        // Attempt to parse the JSON; handle errors
-        let mut modification = match Self::from_json(json_content) {
+        // Attempt to parse the manifest; handle errors
+        let mut modification = match serde_json::from_value::<Modification>(manifest) {
             Ok(modification) => modification,
             Err(err) => {
                 return Err(format!(
-                    "Failed to parse JSON from file at {:?}: {}",
+                    "Failed to parse manifest at {:?}: {}",
                     json_path, err
                 ))
             }
         };
 
-        modification.file_path = file_path;
+        modification
+            .options
+            .check()
+            .map_err(|err| format!("Invalid options in {:?}: {}", json_path, err))?;
+        modification.options.reset();
+        if modification.name.is_empty() {
+            modification.name = source.name();
+        }
+
+        modification.file_path = source.path().clone();
+        modification.source = source;
         modification.enabled = false;
+        modification.signature = check_signature(&modification.source, &modification.hashes);
+        modification.warnings = warnings;
 
         Ok(modification)
     }
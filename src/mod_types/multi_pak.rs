@@ -1,47 +1,234 @@
+use crate::mod_types::{
+    check_signature, manifest, schema, schema::ManifestError, Compatibility, IntegrityProblem,
+    Metadata, ModSource, SignatureCheck, Translations,
+};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
-use std::path::PathBuf;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
 pub struct Pak {
     pub name: String,
     pub description: String,
     pub images: Vec<PathBuf>,
     pub pak: String,
+    #[serde(skip_serializing_if = "Translations::is_empty")]
+    pub translations: Translations,
+    /// The value this pak has on each of the mod's axes, e.g. "Color": "Red"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variant: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
     #[serde(skip)]
     pub path: PathBuf,
+    #[serde(skip)]
+    pub enabled: bool,
+}
+// One dimension a pack varies along, like color or style
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Axis {
+    pub name: String,
+    pub values: Vec<String>,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
 }
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
 pub struct MultiPak {
     pub author: String,
     pub description: String,
     pub version: String,
     pub name: String,
     pub paks: Vec<Pak>,
+    #[serde(skip_serializing_if = "Translations::is_empty")]
+    pub translations: Translations,
+    /// Lets other mods refer to this one in requires and conflicts_with
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts_with: Vec<String>,
+    /// Slots like a hero's skin that only one enabled mod can fill, e.g. "spider-man/suit"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclusive: Vec<String>,
+    /// Lets any number of paks be enabled together instead of picking exactly one
+    #[serde(default)]
+    pub multi_select: bool,
+    /// When set, the pak is picked by choosing a value for each axis instead of from a list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub axes: Vec<Axis>,
+    /// SHA-256 of every pak and image, keyed by their path relative to the root of the mod
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub hashes: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    #[serde(flatten)]
+    pub compatibility: Compatibility,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
     #[serde(skip)]
     pub enabled: bool,
     #[serde(skip)]
     pub path: PathBuf,
     #[serde(skip)]
+    pub source: ModSource,
+    #[serde(skip)]
     pub selected_pak: usize,
+    // Index of the chosen value on each axis
+    #[serde(skip)]
+    pub axis_selection: Vec<usize>,
+    // Files that didn't match their hash the last time they were checked
+    #[serde(skip)]
+    pub integrity_problems: Vec<IntegrityProblem>,
+    // Whether mod.sig holds up, who the key belongs to is up to the manager's keyring
+    #[serde(skip)]
+    pub signature: SignatureCheck,
+    // Problems with the manifest that don't stop the mod from loading, like a missing image
+    #[serde(skip)]
+    pub warnings: Vec<ManifestError>,
 }
 
 impl MultiPak {
     pub fn build(path: PathBuf) -> Result<Self, String> {
-        let json_path: PathBuf = path.join("mod.json");
-        let json_content = std::fs::read_to_string(&json_path).unwrap();
-        let multi_pak: Result<MultiPak, serde_json::Error> = serde_json::from_str(&json_content);
-        let mut modification = multi_pak.unwrap();
+        Self::from_source(ModSource::Directory(path))
+    }
+    pub fn from_source(source: ModSource) -> Result<Self, String> {
+        let manifest_path = manifest::manifest_path(&source);
+        let manifest = manifest::read_manifest(&source)?;
+        let warnings = schema::validate_multi_pak(&source, &manifest)
+            .map_err(|errors| format!("Invalid manifest at {:?}:{}", manifest_path, errors))?;
+        let multi_pak: Result<MultiPak, serde_json::Error> = serde_json::from_value(manifest);
+        let mut modification = multi_pak
+            .map_err(|err| format!("Failed to parse manifest at {:?}: {}", manifest_path, err))?;
         for pak in &mut modification.paks {
-            pak.path = path.join("paks").join(&pak.pak)
+            pak.path = source.display_path(&Pak::entry(&pak.pak));
+            if pak.name.is_empty() {
+                pak.name = pak.pak.clone();
+            }
+        }
+        modification.check_axes()?;
+        // Start out on whatever combination the first pak is
+        modification.axis_selection = modification
+            .axes
+            .iter()
+            .map(|axis| {
+                modification
+                    .paks
+                    .first()
+                    .and_then(|pak| pak.variant.get(&axis.name))
+                    .and_then(|value| axis.values.iter().position(|other| other == value))
+                    .unwrap_or(0)
+            })
+            .collect();
+        if modification.name.is_empty() {
+            modification.name = source.name();
         }
-        modification.path = path;
+        modification.path = source.path().clone();
+        modification.source = source;
         modification.enabled = false;
+        modification.signature = check_signature(&modification.source, &modification.hashes);
+        modification.warnings = warnings;
         Ok(modification)
     }
+    // Every pak's variant has to name a value on each axis, and only values that exist
+    fn check_axes(&self) -> Result<(), String> {
+        for axis in &self.axes {
+            if axis.values.is_empty() {
+                return Err(format!("Axis {} of {} has no values", axis.name, self.name));
+            }
+        }
+        if self.axes.is_empty() {
+            return Ok(());
+        }
+        for pak in &self.paks {
+            for axis in &self.axes {
+                match pak.variant.get(&axis.name) {
+                    Some(value) if axis.values.contains(value) => {}
+                    Some(value) => {
+                        return Err(format!(
+                            "Pak {} uses {} for {}, which is not one of its values",
+                            pak.pak, value, axis.name
+                        ))
+                    }
+                    None => return Err(format!("Pak {} has no value for {}", pak.pak, axis.name)),
+                }
+            }
+            if let Some(name) = pak
+                .variant
+                .keys()
+                .find(|name| !self.axes.iter().any(|axis| &axis.name == *name))
+            {
+                return Err(format!("Pak {} uses unknown axis {}", pak.pak, name));
+            }
+        }
+        Ok(())
+    }
+    pub fn localized_name(&self, locale: &str) -> &str {
+        self.translations.name(locale, &self.name)
+    }
+    pub fn localized_description(&self, locale: &str) -> &str {
+        self.translations.description(locale, &self.description)
+    }
+    // Picks the same paks and axis values as `previous` where they still exist, for a mod that
+    // was built again from its files
+    pub fn keep_selection(&mut self, previous: &MultiPak) {
+        for pak in &mut self.paks {
+            if let Some(previous_pak) = previous.paks.iter().find(|other| other.pak == pak.pak) {
+                pak.enabled = previous_pak.enabled;
+            }
+        }
+        if let Some(index) = previous
+            .paks
+            .get(previous.selected_pak)
+            .and_then(|selected| self.paks.iter().position(|pak| pak.pak == selected.pak))
+        {
+            self.selected_pak = index;
+        }
+        for (axis, selection) in self.axes.iter().zip(self.axis_selection.iter_mut()) {
+            let previous_value = previous
+                .axes
+                .iter()
+                .zip(&previous.axis_selection)
+                .find(|(other, _)| other.name == axis.name)
+                .and_then(|(other, &value)| other.values.get(value));
+            if let Some(value) =
+                previous_value.and_then(|value| axis.values.iter().position(|other| other == value))
+            {
+                *selection = value;
+            }
+        }
+    }
     pub fn selected_pak(&self) -> &Pak {
         &self.paks[self.selected_pak]
     }
+    // The pak matching the values chosen on every axis, if the author made one for them
+    pub fn variant_index(&self) -> Option<usize> {
+        self.paks.iter().position(|pak| {
+            self.axes
+                .iter()
+                .zip(&self.axis_selection)
+                .all(|(axis, &value)| pak.variant.get(&axis.name) == Some(&axis.values[value]))
+        })
+    }
+    // The paks that get deployed when the mod is enabled
+    pub fn chosen_paks(&self) -> Vec<&Pak> {
+        if self.multi_select {
+            self.paks.iter().filter(|pak| pak.enabled).collect()
+        } else if !self.axes.is_empty() {
+            self.variant_index()
+                .map(|index| &self.paks[index])
+                .into_iter()
+                .collect()
+        } else {
+            vec![self.selected_pak()]
+        }
+    }
 }
 
 impl Pak {
@@ -51,10 +238,24 @@ impl Pak {
             description: "".to_string(),
             images: vec![],
             pak,
+            variant: BTreeMap::new(),
+            translations: Translations::default(),
+            extras: Map::new(),
             path: Default::default(),
+            enabled: false,
         }
     }
+    pub fn localized_name(&self, locale: &str) -> &str {
+        self.translations.name(locale, &self.name)
+    }
+    pub fn localized_description(&self, locale: &str) -> &str {
+        self.translations.description(locale, &self.description)
+    }
     pub fn set_name(&mut self, name: String) {
         self.name = name;
     }
+    // Paks live in the "paks" folder of a MultiPak, relative to its root
+    pub fn entry(pak: &str) -> PathBuf {
+        Path::new("paks").join(pak)
+    }
 }
@@ -0,0 +1,314 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupKind {
+    #[default]
+    Any,
+    ExactlyOne,
+    AtMostOne,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct ModOption {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Paks installed with this option, relative to the root of the mod
+    pub paks: Vec<PathBuf>,
+    #[serde(default)]
+    pub default: bool,
+    /// Only available while all of these options are selected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    /// Unavailable while any of these options is selected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+    #[serde(skip)]
+    pub selected: bool,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct OptionGroup {
+    pub name: String,
+    #[serde(default)]
+    pub kind: GroupKind,
+    pub options: Vec<ModOption>,
+    #[serde(flatten)]
+    pub extras: Map<String, Value>,
+}
+
+// The option groups of a mod. Options are referred to by name, so names are unique across groups.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct OptionGroups(pub Vec<OptionGroup>);
+
+impl OptionGroups {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn groups(&self) -> &[OptionGroup] {
+        &self.0
+    }
+    fn options(&self) -> impl Iterator<Item = &ModOption> {
+        self.0.iter().flat_map(|group| group.options.iter())
+    }
+    pub fn check(&self) -> Result<(), String> {
+        let mut names = HashSet::new();
+        for option in self.options() {
+            if !names.insert(option.name.as_str()) {
+                return Err(format!("Option {} is defined more than once", option.name));
+            }
+        }
+        for option in self.options() {
+            if let Some(missing) = option
+                .requires
+                .iter()
+                .chain(&option.excludes)
+                .find(|name| !names.contains(name.as_str()))
+            {
+                return Err(format!(
+                    "Option {} refers to unknown option {}",
+                    option.name, missing
+                ));
+            }
+        }
+        for group in &self.0 {
+            if group.kind == GroupKind::ExactlyOne && group.options.is_empty() {
+                return Err(format!("Group {} has nothing to choose from", group.name));
+            }
+        }
+        Ok(())
+    }
+    fn is_selected(&self, name: &str) -> bool {
+        self.options()
+            .any(|option| option.name == name && option.selected)
+    }
+    pub fn is_available(&self, group: usize, option: usize) -> bool {
+        let option = &self.0[group].options[option];
+        option.requires.iter().all(|name| self.is_selected(name))
+            && !option.excludes.iter().any(|name| self.is_selected(name))
+    }
+    // Goes back to the author's default selection
+    pub fn reset(&mut self) {
+        for group in &mut self.0 {
+            for option in &mut group.options {
+                option.selected = option.default;
+            }
+        }
+        self.resolve();
+    }
+    // Picks what was picked in `previous` wherever the same group and option still exist, for a
+    // mod that was built again from its files
+    pub fn keep_selection(&mut self, previous: &OptionGroups) {
+        for group in &mut self.0 {
+            let Some(previous_group) = previous.0.iter().find(|other| other.name == group.name)
+            else {
+                continue;
+            };
+            for option in &mut group.options {
+                if let Some(previous_option) = previous_group
+                    .options
+                    .iter()
+                    .find(|other| other.name == option.name)
+                {
+                    option.selected = previous_option.selected;
+                }
+            }
+        }
+        self.resolve();
+    }
+    pub fn set_selected(&mut self, group: usize, option: usize, selected: bool) {
+        if selected && !self.is_available(group, option) {
+            return;
+        }
+        let kind = self.0[group].kind;
+        let options = &mut self.0[group].options;
+        match kind {
+            // The only choice of an exactly-one group can't be deselected
+            GroupKind::ExactlyOne if !selected => return,
+            GroupKind::ExactlyOne | GroupKind::AtMostOne if selected => {
+                for other in options.iter_mut() {
+                    other.selected = false;
+                }
+            }
+            _ => {}
+        }
+        options[option].selected = selected;
+        self.resolve();
+    }
+    // Drops selections whose conditions no longer hold and makes sure every exactly-one group
+    // has something picked. Each pass can only change a bounded number of options, so it settles.
+    fn resolve(&mut self) {
+        let option_count = self.options().count();
+        for _ in 0..=option_count {
+            let mut changed = false;
+            for group in 0..self.0.len() {
+                for option in 0..self.0[group].options.len() {
+                    if self.0[group].options[option].selected && !self.is_available(group, option) {
+                        self.0[group].options[option].selected = false;
+                        changed = true;
+                    }
+                }
+                let kind = self.0[group].kind;
+                let selected: Vec<usize> = (0..self.0[group].options.len())
+                    .filter(|&option| self.0[group].options[option].selected)
+                    .collect();
+                if kind != GroupKind::Any && selected.len() > 1 {
+                    for &option in &selected[1..] {
+                        self.0[group].options[option].selected = false;
+                    }
+                    changed = true;
+                }
+                if kind == GroupKind::ExactlyOne && selected.is_empty() {
+                    // Prefer the default when it's available, otherwise the first that is
+                    let options = &self.0[group].options;
+                    let fallback = (0..options.len())
+                        .filter(|&option| self.is_available(group, option))
+                        .min_by_key(|&option| !options[option].default);
+                    if let Some(option) = fallback {
+                        self.0[group].options[option].selected = true;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+    pub fn chosen_paks(&self) -> Vec<&PathBuf> {
+        self.options()
+            .filter(|option| option.selected)
+            .flat_map(|option| option.paks.iter())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(name: &str, default: bool, requires: &[&str], excludes: &[&str]) -> ModOption {
+        ModOption {
+            name: name.to_string(),
+            default,
+            requires: requires.iter().map(|name| name.to_string()).collect(),
+            excludes: excludes.iter().map(|name| name.to_string()).collect(),
+            ..ModOption::default()
+        }
+    }
+
+    fn group(name: &str, kind: GroupKind, options: Vec<ModOption>) -> OptionGroup {
+        OptionGroup {
+            name: name.to_string(),
+            kind,
+            options,
+            ..OptionGroup::default()
+        }
+    }
+
+    fn selected(groups: &OptionGroups) -> Vec<&str> {
+        groups
+            .options()
+            .filter(|option| option.selected)
+            .map(|option| option.name.as_str())
+            .collect()
+    }
+
+    fn body_and_extras() -> OptionGroups {
+        OptionGroups(vec![
+            group(
+                "Body",
+                GroupKind::ExactlyOne,
+                vec![
+                    option("Slim", false, &[], &[]),
+                    option("Bulky", true, &[], &[]),
+                ],
+            ),
+            group(
+                "Extras",
+                GroupKind::Any,
+                vec![
+                    option("Cape", true, &["Bulky"], &[]),
+                    option("Mask", false, &[], &["Cape"]),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn reset_picks_the_defaults() {
+        let mut groups = body_and_extras();
+        groups.check().unwrap();
+        groups.reset();
+        assert_eq!(selected(&groups), ["Bulky", "Cape"]);
+    }
+
+    #[test]
+    fn resolve_drops_options_whose_conditions_no_longer_hold() {
+        let mut groups = body_and_extras();
+        groups.reset();
+        // Cape requires Bulky, so picking Slim takes it away
+        groups.set_selected(0, 0, true);
+        assert_eq!(selected(&groups), ["Slim"]);
+        // Mask is excluded while Cape is picked
+        groups.set_selected(0, 1, true);
+        groups.set_selected(1, 0, true);
+        groups.set_selected(1, 1, true);
+        assert_eq!(selected(&groups), ["Bulky", "Cape"]);
+        // The only pick of an exactly-one group stays
+        groups.set_selected(0, 1, false);
+        assert_eq!(selected(&groups), ["Bulky", "Cape"]);
+    }
+
+    #[test]
+    fn resolve_fills_exactly_one_groups_with_what_is_available() {
+        let mut groups = OptionGroups(vec![
+            group(
+                "Base",
+                GroupKind::AtMostOne,
+                vec![option("Classic", true, &[], &[])],
+            ),
+            group(
+                "Color",
+                GroupKind::ExactlyOne,
+                vec![
+                    option("Red", false, &[], &[]),
+                    option("Blue", true, &["Modern"], &[]),
+                ],
+            ),
+        ]);
+        groups.reset();
+        // Blue is the default but can't be picked without Modern
+        assert_eq!(selected(&groups), ["Classic", "Red"]);
+    }
+
+    #[test]
+    fn check_rejects_duplicate_and_unknown_names() {
+        let duplicate = OptionGroups(vec![group(
+            "Body",
+            GroupKind::Any,
+            vec![
+                option("Slim", false, &[], &[]),
+                option("Slim", false, &[], &[]),
+            ],
+        )]);
+        assert!(duplicate.check().is_err());
+        let unknown = OptionGroups(vec![group(
+            "Body",
+            GroupKind::Any,
+            vec![option("Slim", false, &["Bulky"], &[])],
+        )]);
+        assert!(unknown.check().is_err());
+    }
+}
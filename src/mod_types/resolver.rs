@@ -0,0 +1,279 @@
+use crate::mod_types::{conflicts, find_by_id, shared_slot, ModType};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    // Indices of every mod that should be enabled
+    pub selection: BTreeSet<usize>,
+    // Mods that had to be switched on or off, with the reason why
+    pub added: Vec<(usize, String)>,
+    pub removed: Vec<(usize, String)>,
+}
+
+// Why the mods the user insisted on can't be enabled together. Only the requirement chains
+// leading to the clash are listed.
+#[derive(Debug)]
+pub struct Unsatisfiable {
+    pub reasons: Vec<String>,
+}
+
+impl Display for Unsatisfiable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reasons.join(", "))
+    }
+}
+
+impl Error for Unsatisfiable {}
+
+// A set of mods together with everything they require, remembering who pulled each one in
+struct Closure {
+    members: Vec<usize>,
+    required_by: HashMap<usize, usize>,
+}
+
+impl Closure {
+    fn of(mods: &[ModType], roots: &[usize]) -> Result<Self, Vec<String>> {
+        let mut closure = Closure {
+            members: Vec::new(),
+            required_by: HashMap::new(),
+        };
+        let mut pending: Vec<usize> = roots.to_vec();
+        while let Some(index) = pending.pop() {
+            if closure.members.contains(&index) {
+                continue;
+            }
+            closure.members.push(index);
+            for requires in mods[index].requires() {
+                match find_by_id(mods, requires) {
+                    Some((required, _)) => {
+                        if !closure.members.contains(&required) {
+                            closure.required_by.entry(required).or_insert(index);
+                            pending.push(required);
+                        }
+                    }
+                    None => {
+                        let mut reasons = closure.chain(mods, index);
+                        reasons.push(format!(
+                            "{} requires {}, which is not installed",
+                            mods[index].name(),
+                            requires
+                        ));
+                        return Err(reasons);
+                    }
+                }
+            }
+        }
+        Ok(closure)
+    }
+    // The requirements that lead from a root to `index`
+    fn chain(&self, mods: &[ModType], mut index: usize) -> Vec<String> {
+        let mut chain = Vec::new();
+        while let Some(&parent) = self.required_by.get(&index) {
+            chain.push(format!(
+                "{} requires {}",
+                mods[parent].name(),
+                mods[index].name()
+            ));
+            index = parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+fn clash(mods: &[ModType], a: usize, b: usize) -> Option<String> {
+    if a == b {
+        return None;
+    }
+    if conflicts(&mods[a], &mods[b]) {
+        return Some(format!(
+            "{} conflicts with {}",
+            mods[a].name(),
+            mods[b].name()
+        ));
+    }
+    shared_slot(&mods[a], &mods[b]).map(|slot| {
+        format!(
+            "{} and {} both replace {}",
+            mods[a].name(),
+            mods[b].name(),
+            slot
+        )
+    })
+}
+
+// Mods in `rejected` were switched off by the user and can't be brought back in
+fn rejected_member(mods: &[ModType], closure: &Closure, rejected: &[usize]) -> Option<Vec<String>> {
+    let index = *closure
+        .members
+        .iter()
+        .find(|index| rejected.contains(index))?;
+    let mut reasons = closure.chain(mods, index);
+    reasons.push(format!("{} was switched off", mods[index].name()));
+    Some(reasons)
+}
+
+/// Picks a consistent set of mods to enable.
+///
+/// Everything in `required` is enabled along with what it requires, or the reasons it can't be
+/// are returned. Mods in `preferred`, usually the ones already enabled, are then kept in order
+/// for as long as they fit with what was picked so far. Nothing in `rejected` is enabled.
+pub fn resolve_selection(
+    mods: &[ModType],
+    required: &[usize],
+    preferred: &[usize],
+    rejected: &[usize],
+) -> Result<Resolution, Unsatisfiable> {
+    let closure = Closure::of(mods, required).map_err(|reasons| Unsatisfiable { reasons })?;
+    if let Some(reasons) = rejected_member(mods, &closure, rejected) {
+        return Err(Unsatisfiable { reasons });
+    }
+    for (position, &a) in closure.members.iter().enumerate() {
+        for &b in &closure.members[position + 1..] {
+            if let Some(reason) = clash(mods, a, b) {
+                let mut reasons = closure.chain(mods, a);
+                for step in closure.chain(mods, b) {
+                    if !reasons.contains(&step) {
+                        reasons.push(step);
+                    }
+                }
+                reasons.push(reason);
+                return Err(Unsatisfiable { reasons });
+            }
+        }
+    }
+
+    let mut resolution = Resolution::default();
+    resolution.selection.extend(closure.members.iter().copied());
+    let mut pulled_in = closure.required_by;
+    for &index in preferred {
+        if resolution.selection.contains(&index) || rejected.contains(&index) {
+            continue;
+        }
+        let candidate = match Closure::of(mods, &[index]) {
+            Ok(candidate) => candidate,
+            Err(reasons) => {
+                resolution.removed.push((index, reasons.join(", ")));
+                continue;
+            }
+        };
+        if let Some(reasons) = rejected_member(mods, &candidate, rejected) {
+            resolution.removed.push((index, reasons.join(", ")));
+            continue;
+        }
+        let reason = candidate.members.iter().find_map(|&a| {
+            resolution
+                .selection
+                .iter()
+                .chain(&candidate.members)
+                .find_map(|&b| clash(mods, a, b))
+        });
+        match reason {
+            Some(reason) => resolution.removed.push((index, reason)),
+            None => {
+                for (required, parent) in candidate.required_by {
+                    if !resolution.selection.contains(&required) {
+                        pulled_in.entry(required).or_insert(parent);
+                    }
+                }
+                resolution.selection.extend(candidate.members);
+            }
+        }
+    }
+
+    for &index in &resolution.selection {
+        if required.contains(&index) || preferred.contains(&index) {
+            continue;
+        }
+        let reason = match pulled_in.get(&index) {
+            Some(&parent) => format!("required by {}", mods[parent].name()),
+            None => "required".to_string(),
+        };
+        resolution.added.push((index, reason));
+    }
+    Ok(resolution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mod_types::Modification;
+
+    fn complete(id: &str, requires: &[&str], conflicts_with: &[&str]) -> ModType {
+        let mut modification = Modification::new();
+        modification.name = id.to_uppercase();
+        modification.id = id.to_string();
+        modification.requires = requires.iter().map(|id| id.to_string()).collect();
+        modification.conflicts_with = conflicts_with.iter().map(|id| id.to_string()).collect();
+        ModType::Complete(modification)
+    }
+
+    #[test]
+    fn requirements_are_pulled_in_with_who_needs_them() {
+        let mods = [
+            complete("suit", &["body"], &[]),
+            complete("body", &["skeleton"], &[]),
+            complete("skeleton", &[], &[]),
+        ];
+        let resolution = resolve_selection(&mods, &[0], &[], &[]).unwrap();
+        assert_eq!(resolution.selection, BTreeSet::from([0, 1, 2]));
+        assert_eq!(
+            resolution.added,
+            [
+                (1, "required by SUIT".to_string()),
+                (2, "required by BODY".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn clashes_are_explained_through_the_requirement_chain() {
+        let mods = [
+            complete("suit", &["body"], &[]),
+            complete("body", &["skeleton"], &[]),
+            complete("skeleton", &[], &["mask"]),
+            complete("mask", &[], &[]),
+        ];
+        let err = resolve_selection(&mods, &[0, 3], &[], &[]).unwrap_err();
+        assert_eq!(
+            err.reasons,
+            [
+                "SUIT requires BODY",
+                "BODY requires SKELETON",
+                "MASK conflicts with SKELETON"
+            ]
+        );
+
+        let err = resolve_selection(&mods, &[0], &[], &[2]).unwrap_err();
+        assert_eq!(
+            err.reasons,
+            [
+                "SUIT requires BODY",
+                "BODY requires SKELETON",
+                "SKELETON was switched off"
+            ]
+        );
+
+        let mods = [complete("suit", &["body"], &[])];
+        let err = resolve_selection(&mods, &[0], &[], &[]).unwrap_err();
+        assert_eq!(err.reasons, ["SUIT requires body, which is not installed"]);
+    }
+
+    #[test]
+    fn preferred_mods_that_no_longer_fit_are_removed() {
+        let mods = [
+            complete("suit", &[], &["mask"]),
+            complete("mask", &[], &[]),
+            complete("cape", &[], &[]),
+        ];
+        let resolution = resolve_selection(&mods, &[0], &[1, 2], &[]).unwrap();
+        assert_eq!(resolution.selection, BTreeSet::from([0, 2]));
+        assert_eq!(
+            resolution.removed,
+            [(1, "MASK conflicts with SUIT".to_string())]
+        );
+        assert!(resolution.added.is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::mod_types::manifest::FORMAT_VERSION;
+use crate::mod_types::{
+    is_locale, is_sha256, Compatibility, Metadata, ModSource, Modification, MultiPak, Pak,
+    Translations,
+};
+use jsonschema::Validator;
+use schemars::schema_for;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Name of the schema file the builder writes next to mod.json.
+pub const SCHEMA_FILE: &str = "mod.schema.json";
+
+// A single problem with a manifest, like "paks[2].pak: paks/red_P.pak does not exist"
+#[derive(Debug, Clone)]
+pub struct ManifestError {
+    // Where in mod.json the problem is, empty for the manifest as a whole
+    pub field: String,
+    pub message: String,
+}
+
+impl ManifestError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ManifestErrors(pub Vec<ManifestError>);
+
+impl Display for ManifestErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for error in &self.0 {
+            write!(f, "\n  {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ManifestErrors {}
+
+// The generated schemas only know the struct fields, so add the ones the manifest
+// module writes around them
+fn finish(mut schema: Value, title: &str) -> Value {
+    schema["title"] = json!(title);
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert(
+            "$schema".to_string(),
+            json!({ "type": "string", "description": "Points editors at this schema" }),
+        );
+        properties.insert(
+            "format_version".to_string(),
+            json!({
+                "type": "integer",
+                "minimum": 1,
+                "maximum": FORMAT_VERSION,
+                "description": "Version of the mod.json format the file was written in",
+            }),
+        );
+    }
+    schema
+}
+
+pub fn modification_schema() -> Value {
+    let schema = serde_json::to_value(schema_for!(Modification)).unwrap();
+    finish(schema, "Marvel Rivals mod")
+}
+
+pub fn multi_pak_schema() -> Value {
+    let schema = serde_json::to_value(schema_for!(MultiPak)).unwrap();
+    finish(schema, "Marvel Rivals multiple .pak mod")
+}
+
+/// Writes the schema for a mod into `mod_dir`, so editors can check mod.json while it's edited.
+pub fn write_schema(mod_dir: &Path, multi_pak: bool) -> Result<(), String> {
+    let schema = if multi_pak {
+        multi_pak_schema()
+    } else {
+        modification_schema()
+    };
+    let path = mod_dir.join(SCHEMA_FILE);
+    let json = serde_json::to_string_pretty(&schema).map_err(|err| err.to_string())?;
+    fs::write(&path, json).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+}
+
+// Turns a JSON pointer like /paks/2/images/0 into paks[2].images[0]
+fn field_name(pointer: &str) -> String {
+    let mut field = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.parse::<usize>().is_ok() {
+            field.push_str(&format!("[{}]", segment));
+        } else {
+            if !field.is_empty() {
+                field.push('.');
+            }
+            field.push_str(&segment);
+        }
+    }
+    field
+}
+
+fn check_schema(validator: &Validator, manifest: &Value) -> Result<(), ManifestErrors> {
+    let errors: Vec<ManifestError> = validator
+        .iter_errors(manifest)
+        .map(|error| {
+            ManifestError::new(field_name(error.instance_path.as_str()), error.to_string())
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ManifestErrors(errors))
+    }
+}
+
+fn check_images<'a>(
+    source: &ModSource,
+    field: &str,
+    images: impl IntoIterator<Item = &'a std::path::PathBuf>,
+    errors: &mut Vec<ManifestError>,
+) {
+    for (index, image) in images.into_iter().enumerate() {
+        if !source.exists(image) {
+            errors.push(ManifestError::new(
+                format!("{}[{}]", field, index),
+                format!("image {:?} does not exist", image),
+            ));
+        }
+    }
+}
+
+fn check_translations(field: &str, translations: &Translations, errors: &mut Vec<ManifestError>) {
+    for locale in translations.0.keys() {
+        if !is_locale(locale) {
+            errors.push(ManifestError::new(
+                format!("{}.{}", field, locale),
+                format!("{} is not a language like fr or pt-BR", locale),
+            ));
+        }
+    }
+}
+
+fn check_compatibility(compatibility: &Compatibility, errors: &mut Vec<ManifestError>) {
+    if !compatibility.has_valid_version() {
+        errors.push(ManifestError::new(
+            "min_manager_version",
+            format!(
+                "{} is not a version like 0.2.0",
+                compatibility.min_manager_version
+            ),
+        ));
+    }
+    for (index, range) in compatibility.game_build.iter().enumerate() {
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                errors.push(ManifestError::new(
+                    format!("game_build[{}]", index),
+                    format!("min {} is above max {}", min, max),
+                ));
+            }
+        }
+    }
+}
+
+fn check_hashes(hashes: &BTreeMap<String, String>, errors: &mut Vec<ManifestError>) {
+    for (path, hash) in hashes {
+        if !is_sha256(hash) {
+            errors.push(ManifestError::new(
+                format!("hashes.{}", path),
+                format!("{} is not a SHA-256 hash", hash),
+            ));
+        }
+    }
+}
+
+// Nothing here stops the mod from working, so these only ever end up as warnings
+fn check_metadata(source: &ModSource, metadata: &Metadata, errors: &mut Vec<ManifestError>) {
+    for (index, contributor) in metadata.contributors.iter().enumerate() {
+        if contributor.name.trim().is_empty() {
+            errors.push(ManifestError::new(
+                format!("contributors[{}].name", index),
+                "contributors need a name",
+            ));
+        }
+    }
+    if !metadata.has_valid_date() {
+        errors.push(ManifestError::new(
+            "updated",
+            format!("{} is not a date like 2025-01-31", metadata.updated),
+        ));
+    }
+    if let Some(cover) = &metadata.cover {
+        if !source.exists(cover) {
+            errors.push(ManifestError::new(
+                "cover",
+                format!("image {:?} does not exist", cover),
+            ));
+        }
+    }
+}
+
+// Problems that only change how a mod is shown, like a missing image or a badly written date,
+// are returned as warnings instead, so they don't keep an otherwise working mod from loading.
+fn finish_checks(
+    errors: Vec<ManifestError>,
+    warnings: Vec<ManifestError>,
+) -> Result<Vec<ManifestError>, ManifestErrors> {
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(ManifestErrors(errors))
+    }
+}
+
+/// Checks the manifest of a complete mod against its schema and the files that came with it,
+/// returning the warnings if it can be loaded.
+pub fn validate_modification(
+    source: &ModSource,
+    manifest: &Value,
+) -> Result<Vec<ManifestError>, ManifestErrors> {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    let validator =
+        VALIDATOR.get_or_init(|| jsonschema::validator_for(&modification_schema()).unwrap());
+    check_schema(validator, manifest)?;
+    let modification: Modification = serde_json::from_value(manifest.clone())
+        .map_err(|err| ManifestErrors(vec![ManifestError::new("", err.to_string())]))?;
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    check_images(source, "images", &modification.images, &mut warnings);
+    check_metadata(source, &modification.metadata, &mut warnings);
+    check_compatibility(&modification.compatibility, &mut errors);
+    check_hashes(&modification.hashes, &mut errors);
+    check_translations("translations", &modification.translations, &mut warnings);
+    for (group_index, group) in modification.options.groups().iter().enumerate() {
+        for (option_index, option) in group.options.iter().enumerate() {
+            for (pak_index, pak) in option.paks.iter().enumerate() {
+                if !source.exists(pak) {
+                    errors.push(ManifestError::new(
+                        format!(
+                            "options[{}].options[{}].paks[{}]",
+                            group_index, option_index, pak_index
+                        ),
+                        format!("{} needs {:?}, which does not exist", option.name, pak),
+                    ));
+                }
+            }
+        }
+    }
+    let has_root_pak = source
+        .list(Path::new(""))
+        .iter()
+        .any(|path| path.to_string_lossy().ends_with(".pak"));
+    if !has_root_pak && modification.options.is_empty() {
+        errors.push(ManifestError::new(
+            "",
+            "there is no .pak file next to mod.json",
+        ));
+    }
+    finish_checks(errors, warnings)
+}
+
+/// Checks the manifest of a multiple .pak mod against its schema and the files that came with it,
+/// returning the warnings if it can be loaded.
+pub fn validate_multi_pak(
+    source: &ModSource,
+    manifest: &Value,
+) -> Result<Vec<ManifestError>, ManifestErrors> {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    let validator =
+        VALIDATOR.get_or_init(|| jsonschema::validator_for(&multi_pak_schema()).unwrap());
+    check_schema(validator, manifest)?;
+    let multi_pak: MultiPak = serde_json::from_value(manifest.clone())
+        .map_err(|err| ManifestErrors(vec![ManifestError::new("", err.to_string())]))?;
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    if multi_pak.paks.is_empty() {
+        errors.push(ManifestError::new("paks", "no paks are listed"));
+    }
+    check_metadata(source, &multi_pak.metadata, &mut warnings);
+    check_compatibility(&multi_pak.compatibility, &mut errors);
+    check_hashes(&multi_pak.hashes, &mut errors);
+    check_translations("translations", &multi_pak.translations, &mut warnings);
+    let mut files: HashMap<&str, usize> = HashMap::new();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for (index, pak) in multi_pak.paks.iter().enumerate() {
+        if pak.pak.is_empty() {
+            errors.push(ManifestError::new(
+                format!("paks[{}].pak", index),
+                "no .pak file is given",
+            ));
+        } else if let Some(first) = files.get(pak.pak.as_str()) {
+            errors.push(ManifestError::new(
+                format!("paks[{}].pak", index),
+                format!("{} is already listed as paks[{}]", pak.pak, first),
+            ));
+        } else if !source.exists(&Pak::entry(&pak.pak)) {
+            errors.push(ManifestError::new(
+                format!("paks[{}].pak", index),
+                format!("{:?} does not exist", Pak::entry(&pak.pak)),
+            ));
+        }
+        files.entry(&pak.pak).or_insert(index);
+        // Unnamed paks are called after their file when loaded
+        if !pak.name.is_empty() {
+            let first = *names.entry(&pak.name).or_insert(index);
+            if first != index {
+                errors.push(ManifestError::new(
+                    format!("paks[{}].name", index),
+                    format!("paks[{}] is already called {}", first, pak.name),
+                ));
+            }
+        }
+        check_translations(
+            &format!("paks[{}].translations", index),
+            &pak.translations,
+            &mut warnings,
+        );
+        check_images(
+            source,
+            &format!("paks[{}].images", index),
+            &pak.images,
+            &mut warnings,
+        );
+    }
+    finish_checks(errors, warnings)
+}
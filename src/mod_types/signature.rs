@@ -0,0 +1,203 @@
+use crate::mod_types::{manifest, ModSource};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the detached signature the packager writes next to the manifest.
+pub const SIGNATURE_FILE: &str = "mod.sig";
+
+/// What a signature vouches for, shown wherever one is made or checked.
+pub const SIGNATURE_COVERAGE: &str = "The signature covers the manifest and, through its \
+    hashes, every pak and image. Any other files in the mod aren't checked.";
+
+/// Name of the file the packager keeps the author's signing key in.
+pub const AUTHOR_KEY_FILE: &str = "author_key.json";
+
+/// Where the signing key is kept, in the user's config folder so it never ends up next to a
+/// mod that is being packaged and shared.
+pub fn author_key_path() -> Option<PathBuf> {
+    let config_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }?;
+    Some(
+        config_dir
+            .join(env!("CARGO_PKG_NAME"))
+            .join(AUTHOR_KEY_FILE),
+    )
+}
+
+// What mod.sig holds. The signature is over the manifest exactly as it is on disk, which
+// includes the hashes of every pak and image, so it covers the files too.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DetachedSignature {
+    pub author: String,
+    pub public_key: String,
+    pub signature: String,
+}
+
+// Whether a mod's signature holds up, before asking who the key belongs to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SignatureCheck {
+    #[default]
+    Unsigned,
+    Signed {
+        author: String,
+        public_key: String,
+    },
+    Invalid {
+        reason: String,
+    },
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
+
+fn parse_public_key(hex: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = from_hex(hex)?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// Whether `hex` is an Ed25519 public key as written in mod.sig.
+pub fn is_public_key(hex: &str) -> bool {
+    parse_public_key(hex).is_some()
+}
+
+fn manifest_bytes(source: &ModSource) -> Result<Vec<u8>, String> {
+    let format = manifest::find(source).ok_or_else(|| "the mod has no manifest".to_string())?;
+    source.read_bytes(Path::new(format.file_name()))
+}
+
+/// Checks mod.sig against the manifest. A signature is only worth something if the manifest
+/// lists the hashes of the files, otherwise the paks could be swapped without breaking it.
+pub fn check_signature(source: &ModSource, hashes: &BTreeMap<String, String>) -> SignatureCheck {
+    if !source.exists(Path::new(SIGNATURE_FILE)) {
+        return SignatureCheck::Unsigned;
+    }
+    let invalid = |reason: String| SignatureCheck::Invalid { reason };
+    let detached: DetachedSignature = match source
+        .read_to_string(Path::new(SIGNATURE_FILE))
+        .and_then(|json| serde_json::from_str(&json).map_err(|err| err.to_string()))
+    {
+        Ok(detached) => detached,
+        Err(err) => return invalid(format!("{} couldn't be read: {}", SIGNATURE_FILE, err)),
+    };
+    let Some(public_key) = parse_public_key(&detached.public_key) else {
+        return invalid(format!(
+            "{} is not an Ed25519 public key",
+            detached.public_key
+        ));
+    };
+    let Some(signature) =
+        from_hex(&detached.signature).and_then(|bytes| Signature::from_slice(&bytes).ok())
+    else {
+        return invalid("the signature is not an Ed25519 signature".to_string());
+    };
+    if hashes.is_empty() {
+        return invalid("the manifest lists no file hashes for the signature to cover".to_string());
+    }
+    let message = match manifest_bytes(source) {
+        Ok(message) => message,
+        Err(err) => return invalid(err),
+    };
+    match public_key.verify(&message, &signature) {
+        Ok(()) => SignatureCheck::Signed {
+            author: detached.author,
+            public_key: detached.public_key,
+        },
+        Err(_) => invalid("the manifest was changed after it was signed".to_string()),
+    }
+}
+
+// The key a mod author signs their mods with, kept by the packager
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthorKey {
+    pub author: String,
+    secret_key: String,
+}
+
+impl AuthorKey {
+    pub fn generate(author: &str) -> Result<Self, String> {
+        let mut secret_key = [0u8; 32];
+        getrandom::getrandom(&mut secret_key)
+            .map_err(|err| format!("Failed to generate a key: {}", err))?;
+        Ok(Self {
+            author: author.to_string(),
+            secret_key: to_hex(&secret_key),
+        })
+    }
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {:?}: {}", path, err))?;
+        let key: Self = serde_json::from_str(&json)
+            .map_err(|err| format!("Failed to parse {:?}: {}", path, err))?;
+        key.signing_key()?;
+        Ok(key)
+    }
+    // Only readable by the user on Unix, the key is what proves a mod came from them
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        let write = || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+                options.mode(0o600);
+                // The mode is only applied to new files
+                if path.exists() {
+                    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+                }
+            }
+            options.open(path)?.write_all(json.as_bytes())
+        };
+        write().map_err(|err: std::io::Error| format!("Failed to write {:?}: {}", path, err))
+    }
+    fn signing_key(&self) -> Result<SigningKey, String> {
+        let bytes: [u8; 32] = from_hex(&self.secret_key)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "The secret key is not an Ed25519 key".to_string())?;
+        Ok(SigningKey::from_bytes(&bytes))
+    }
+    // What others add to their trusted authors
+    pub fn public_key(&self) -> String {
+        self.signing_key()
+            .map(|key| to_hex(key.verifying_key().as_bytes()))
+            .unwrap_or_default()
+    }
+    /// Signs the manifest in `mod_dir`, writing mod.sig next to it.
+    pub fn sign(&self, mod_dir: &Path) -> Result<(), String> {
+        let message = manifest_bytes(&ModSource::Directory(mod_dir.to_path_buf()))?;
+        let signature = self.signing_key()?.sign(&message);
+        let detached = DetachedSignature {
+            author: self.author.clone(),
+            public_key: self.public_key(),
+            signature: to_hex(&signature.to_bytes()),
+        };
+        let json = serde_json::to_string_pretty(&detached).map_err(|err| err.to_string())?;
+        let path = mod_dir.join(SIGNATURE_FILE);
+        fs::write(&path, json).map_err(|err| format!("Failed to write {:?}: {}", path, err))
+    }
+}
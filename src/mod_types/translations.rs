@@ -0,0 +1,118 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Translation {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+impl Translation {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.description.is_empty()
+    }
+}
+
+// Names and descriptions keyed by locale, like "fr" or "pt-BR". Whatever isn't translated
+// falls back to the untranslated name and description.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Translations(pub BTreeMap<String, Translation>);
+
+// "pt_BR.UTF-8" and "pt-br" both become "pt-br", so locales from the system and from
+// manifests can be compared
+fn normalize(locale: &str) -> String {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    locale.replace('_', "-").to_lowercase()
+}
+
+fn language(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or_default()
+}
+
+/// Whether `locale` looks like a language tag such as "fr" or "pt-BR".
+pub fn is_locale(locale: &str) -> bool {
+    let mut parts = locale.split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.bytes().all(|b| b.is_ascii_alphabetic())
+        && parts.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
+impl Translations {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    // Tries the exact locale, then the bare language, then any other region of that language
+    fn find<'a>(&'a self, locale: &str, field: fn(&Translation) -> &str) -> Option<&'a str> {
+        let locale = normalize(locale);
+        if locale.is_empty() {
+            return None;
+        }
+        let translated = |matches: &dyn Fn(&str) -> bool| {
+            self.0
+                .iter()
+                .filter(|(key, _)| matches(&normalize(key)))
+                .map(|(_, translation)| field(translation))
+                .find(|text| !text.is_empty())
+        };
+        translated(&|key| key == locale)
+            .or_else(|| translated(&|key| key == language(&locale)))
+            .or_else(|| translated(&|key| language(key) == language(&locale)))
+    }
+    pub fn name<'a>(&'a self, locale: &str, untranslated: &'a str) -> &'a str {
+        self.find(locale, |translation| &translation.name)
+            .unwrap_or(untranslated)
+    }
+    pub fn description<'a>(&'a self, locale: &str, untranslated: &'a str) -> &'a str {
+        self.find(locale, |translation| &translation.description)
+            .unwrap_or(untranslated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translations(entries: &[(&str, &str, &str)]) -> Translations {
+        Translations(
+            entries
+                .iter()
+                .map(|(locale, name, description)| {
+                    let translation = Translation {
+                        name: name.to_string(),
+                        description: description.to_string(),
+                    };
+                    (locale.to_string(), translation)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn exact_locale_then_language_then_any_region() {
+        let translations = translations(&[
+            ("pt", "Traje", ""),
+            ("pt-BR", "Roupa", "Descrição"),
+            ("fr-CA", "Costume", ""),
+        ]);
+        assert_eq!(translations.name("pt_BR.UTF-8", "Suit"), "Roupa");
+        assert_eq!(translations.name("pt-PT", "Suit"), "Traje");
+        assert_eq!(translations.name("fr-FR", "Suit"), "Costume");
+        assert_eq!(translations.name("de", "Suit"), "Suit");
+        assert_eq!(translations.name("", "Suit"), "Suit");
+    }
+
+    #[test]
+    fn empty_fields_fall_through() {
+        let translations = translations(&[("pt", "Traje", ""), ("pt-BR", "Roupa", "Descrição")]);
+        // pt has no description, so another region of the language fills in
+        assert_eq!(translations.description("pt", "Suit"), "Descrição");
+        assert_eq!(translations.description("de", "Suit"), "Suit");
+    }
+}
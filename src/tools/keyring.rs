@@ -0,0 +1,95 @@
+use crate::mod_types::{is_public_key, IntegrityProblem, SignatureCheck};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustedAuthor {
+    pub name: String,
+    pub public_key: String,
+}
+
+// Authors whose signed mods are shown as verified, saved as trusted_authors.json next to the
+// executable
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keyring {
+    pub authors: Vec<TrustedAuthor>,
+}
+
+// What the manager shows about where a mod came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    // Signed by a trusted author and the files still match
+    Verified { author: String },
+    // Unsigned, or signed with a key that isn't trusted yet
+    Unknown { signed_by: Option<String> },
+    Invalid { reason: String },
+}
+
+impl Keyring {
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                eprintln!("Failed to parse trusted authors {:?}: {}", path, err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+    pub fn save(&self, path: &Path) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => fs::write(path, json).unwrap_or_else(|err| {
+                eprintln!("Failed to save trusted authors {:?}: {}", path, err)
+            }),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    pub fn find(&self, public_key: &str) -> Option<&TrustedAuthor> {
+        self.authors
+            .iter()
+            .find(|author| author.public_key.eq_ignore_ascii_case(public_key))
+    }
+    pub fn trust(&mut self, name: &str, public_key: &str) -> Result<(), String> {
+        let public_key = public_key.trim().to_lowercase();
+        if !is_public_key(&public_key) {
+            return Err(format!("{} is not an Ed25519 public key", public_key));
+        }
+        if self.find(&public_key).is_none() {
+            self.authors.push(TrustedAuthor {
+                name: name.trim().to_string(),
+                public_key,
+            });
+        }
+        Ok(())
+    }
+    pub fn status(
+        &self,
+        check: &SignatureCheck,
+        integrity_problems: &[IntegrityProblem],
+    ) -> SignatureStatus {
+        match check {
+            SignatureCheck::Unsigned => SignatureStatus::Unknown { signed_by: None },
+            SignatureCheck::Invalid { reason } => SignatureStatus::Invalid {
+                reason: reason.clone(),
+            },
+            // The signature covers the hashes, so files that don't match them or that they don't
+            // list break it too
+            SignatureCheck::Signed { .. } if !integrity_problems.is_empty() => {
+                SignatureStatus::Invalid {
+                    reason: "files changed or were added after the mod was signed".to_string(),
+                }
+            }
+            SignatureCheck::Signed { author, public_key } => match self.find(public_key) {
+                // The name from the keyring, mod.sig could claim to be anyone
+                Some(trusted) => SignatureStatus::Verified {
+                    author: trusted.name.clone(),
+                },
+                None => SignatureStatus::Unknown {
+                    signed_by: Some(author.clone()),
+                },
+            },
+        }
+    }
+}
@@ -1,27 +1,123 @@
+use crate::archive;
+use crate::mod_types::schema::ManifestError;
 use crate::mod_types::LoosePak;
 use crate::mod_types::ModType::*;
 use crate::mod_types::MultiPak;
 use crate::mod_types::*;
+use crate::tools::{Keyring, Settings, SignatureStatus};
 use eframe::epaint::TextureHandle;
 use eframe::Frame;
 use egui::Context;
 use image::GenericImageView;
-use sevenz_rust::decompress_file as decompress_7z;
-use std::fmt::Debug;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
 use std::fs;
-use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use zip::read::ZipArchive;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+// Images decoded for the mod details, keyed by their display path
+#[derive(Default)]
+struct TextureCache(HashMap<PathBuf, Option<TextureHandle>>);
+impl TextureCache {
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+impl Debug for TextureCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.keys()).finish()
+    }
+}
+
+// Why Load Mods left ~mods as it was
+#[derive(Debug)]
+pub enum LoadError {
+    InvalidGameDirectory,
+    // Enabled mods' files are still being checked in the background
+    Verifying,
+    // Enabled mods have requirements, conflicts or broken files that weren't overridden
+    Unresolved,
+    NoSpace(archive::InsufficientSpaceError),
+    // Copying a file failed part way, what was copied was taken out again
+    Deploy(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::InvalidGameDirectory => write!(f, "Check the Game Directory."),
+            LoadError::Verifying => write!(f, "Try again once their files have been checked."),
+            LoadError::Unresolved => write!(f, "Resolve the problems below first."),
+            LoadError::NoSpace(err) => write!(f, "{}", err),
+            LoadError::Deploy(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
 
 #[derive(Default, Debug)]
 pub struct ModManager {
     modifications: Vec<ModType>,
     pub mod_directory: PathBuf,
     pub game_directory: PathBuf,
+    pub settings: Settings,
+    pub settings_path: PathBuf,
+    pub keyring: Keyring,
+    pub keyring_path: PathBuf,
+    // The author being typed into the trusted authors in Settings
+    new_author_name: String,
+    new_author_key: String,
+    show_settings: bool,
     mod_load_status: String,
     selected_mod_index: Option<usize>,
     current_image: usize,
+    archive_passwords: HashMap<PathBuf, String>,
+    // Encrypted archives waiting on a password, with the reason to show in the prompt
+    password_requests: Vec<(PathBuf, String)>,
+    password_input: String,
+    // Archives the user chose to extract despite the unsafe file warning
+    approved_unsafe_archives: HashSet<PathBuf>,
+    unsafe_archive_reports: Vec<archive::UnsafeContentError>,
+    // Archives that weren't extracted for lack of disk space, until the next refresh
+    disk_space_errors: Vec<archive::InsufficientSpaceError>,
+    // Why the last Load Mods didn't deploy anything
+    load_error: Option<LoadError>,
+    extraction_pool: archive::ExtractionPool,
+    // Checks mods' files against their hashes without holding up the window
+    verifier: FileVerifier,
+    textures: TextureCache,
+    // The mod the cached textures were loaded for, they're dropped once another is selected
+    textures_for: Option<PathBuf>,
+    // FOMOD installers waiting for the user to go through their steps
+    fomod_installers: Vec<FomodInstaller>,
+    fomod_focus: Option<(usize, usize)>,
+    // Installers that couldn't install their mod, until the next refresh
+    install_errors: Vec<String>,
+    dependency_problems: Vec<DependencyProblem>,
+    // What the resolver changed the last time the enabled mods changed
+    resolution_notes: Vec<String>,
+    // Mods that won't work with the installed game build or this manager
+    incompatibilities: Vec<(usize, Incompatibility)>,
+    // Read from Steam's appmanifest, None if it couldn't be found
+    game_build: Option<u64>,
+    // Lets mods be loaded despite the problems above
+    ignore_dependency_problems: bool,
+    // What verifying the last load found, None if it wasn't verified
+    deployment_report: Option<DeploymentReport>,
+    // What the last load copied into ~mods and under which names
+    deployment: Deployment,
+    // How ~mods has changed since then, shown until re-synced or dismissed
+    drift: Vec<Drift>,
+    show_drift: bool,
+    // Remove Extra Files was clicked and is waiting to be confirmed
+    confirm_remove_extra: bool,
+    // Recorded files Re-sync couldn't copy again
+    restore_errors: Vec<String>,
+    // Launch Game was clicked while ~mods had drifted
+    launch_pending: bool,
 }
 
 impl ModManager {
@@ -36,6 +132,107 @@ impl ModManager {
             .spawn()
             .unwrap();
     }
+    pub fn refresh_game_build(&mut self) {
+        self.game_build = match installed_game_build(&self.game_directory) {
+            Ok(build) => Some(build),
+            Err(err) => {
+                eprintln!("Failed to find the game build: {}", err);
+                None
+            }
+        };
+    }
+    // Adds a scanned mod, checking its files first if the settings ask for it. Signed mods are
+    // always checked, their signature only vouches for files that match the hashes. A mod built
+    // again from a path that's already listed takes the old one's place and keeps its choices.
+    fn push_mod(&mut self, mut modification: ModType) {
+        let verify = self.settings.verify_on_scan || modification.is_signed();
+        let listed = self
+            .modifications
+            .iter()
+            .position(|listed| listed.path() == modification.path());
+        match listed {
+            Some(index) => {
+                modification.keep_state_from(&self.modifications[index]);
+                self.modifications[index] = modification;
+                // The files behind its images may have changed
+                self.textures.clear();
+            }
+            None => self.modifications.push(modification),
+        }
+        if verify {
+            let index = listed.unwrap_or(self.modifications.len() - 1);
+            self.queue_verification(index);
+        }
+    }
+    fn queue_verification(&mut self, index: usize) {
+        let modification = &self.modifications[index];
+        if let Some((source, hashes)) = modification.hashes() {
+            if !hashes.is_empty() {
+                self.verifier
+                    .submit(modification.path().clone(), source.clone(), hashes.clone());
+            }
+        }
+    }
+    fn forget_textures_of_other_mods(&mut self) {
+        let selected = self
+            .selected_mod_index
+            .and_then(|index| self.modifications.get(index))
+            .map(|modification| modification.path().clone());
+        if selected != self.textures_for {
+            self.textures.clear();
+            self.textures_for = selected;
+        }
+    }
+    // Applies the checks that finished since the last frame to the mods they were for
+    fn poll_verifications(&mut self) {
+        for outcome in self.verifier.poll() {
+            if let Some(modification) = self
+                .modifications
+                .iter_mut()
+                .find(|modification| modification.path() == &outcome.path)
+            {
+                modification.set_integrity_problems(outcome.problems);
+            }
+        }
+    }
+    // Whether an enabled mod's files are still being checked, loading has to wait for them
+    fn is_verifying_enabled(&self) -> bool {
+        self.modifications.iter().any(|modification| {
+            modification.is_enabled() && self.verifier.is_pending(modification.path())
+        })
+    }
+    fn show_verification_progress(&self, ui: &mut egui::Ui) {
+        let Some((done, queued)) = self.verifier.progress() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Verifying mod files");
+            ui.add(
+                egui::ProgressBar::new(done as f32 / queued as f32)
+                    .desired_width(250.0)
+                    .text(format!("{} / {} mods", done, queued)),
+            );
+        });
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+    // Removes the mods `keep` says no to, keeping the same mod selected if it's still there
+    fn remove_mods(&mut self, mut keep: impl FnMut(&ModType) -> bool) {
+        let selected = self
+            .selected_mod_index
+            .and_then(|index| self.modifications.get(index))
+            .map(|modification| modification.path().clone());
+        self.modifications.retain(|modification| keep(modification));
+        self.selected_mod_index = selected.and_then(|path| {
+            self.modifications
+                .iter()
+                .position(|modification| modification.path() == &path)
+        });
+    }
+    pub fn verify_mods(&mut self) {
+        for index in 0..self.modifications.len() {
+            self.queue_verification(index);
+        }
+    }
     pub fn validate_game_directory(&self) -> bool {
         if self.game_directory.exists() {
             if self.game_directory.join("MarvelGame").exists() {
@@ -48,103 +245,380 @@ impl ModManager {
         let game_mod_directory = self
             .game_directory
             .join(r"MarvelGame\Marvel\Content\Paks\~mods");
-        fs::remove_dir_all(&game_mod_directory).unwrap_or_else(|err| {
-            eprintln!(
-                "Failed to remove directory {:?}: {}",
-                game_mod_directory, err
-            )
-        });
+        // Only what the manager deployed is removed, renamed files included. Without a record
+        // of it the whole folder goes, as it always did.
+        match Deployment::load(&game_mod_directory) {
+            Some(deployment) => deployment.remove(&game_mod_directory),
+            None => fs::remove_dir_all(&game_mod_directory).unwrap_or_else(|err| {
+                eprintln!(
+                    "Failed to remove directory {:?}: {}",
+                    game_mod_directory, err
+                )
+            }),
+        }
     }
-    pub fn load_mods(&mut self) {
+    // Copies the enabled mods into ~mods, refusing before anything is removed if the drive
+    // doesn't have room for them. If a copy fails the files copied so far are removed and the
+    // previously deployed mods are put back. Mods with unresolved problems are refused unless
+    // `ignore_problems` says to load them anyway.
+    pub fn load_mods(&mut self, ignore_problems: bool) -> Result<(), LoadError> {
         if !self.validate_game_directory() {
             eprintln!("Game directory is invalid.");
-            return;
+            return Err(LoadError::InvalidGameDirectory);
+        }
+        if self.is_verifying_enabled() {
+            return Err(LoadError::Verifying);
+        }
+        if self.has_unresolved_problems() && !ignore_problems {
+            return Err(LoadError::Unresolved);
         }
-        self.clear_mod_directory();
         let game_mod_directory = self
             .game_directory
             .join(r"MarvelGame\Marvel\Content\Paks\~mods");
-        fs::create_dir_all(&game_mod_directory).unwrap_or_else(|err| {
-            eprintln!(
-                "Failed to create directory {:?}: {}",
-                game_mod_directory, err
-            )
-        });
+        // Each file to copy as its mod and the file within it
+        let mut files: Vec<(&ModType, &ModSource, PathBuf)> = Vec::new();
         for mod_type in &self.modifications {
             match mod_type {
                 LoosePak(lp) => {
                     if lp.enabled {
-                        let destination_path =
-                            game_mod_directory.join(&lp.path().file_name().unwrap());
-                        fs::copy(&lp.path(), &destination_path).unwrap_or_else(|err| {
-                            panic!("Failed to copy {:?}: {}", &lp.path(), err)
-                        });
+                        files.push((mod_type, &lp.source, lp.entry.clone()));
                     }
                 }
                 Complete(cm) => {
                     if cm.enabled {
-                        let destination_path =
-                            game_mod_directory.join(&cm.pak_path().file_name().unwrap());
-                        fs::copy(&cm.pak_path(), &destination_path).unwrap_or_else(|err| {
-                            panic!("Failed to copy {:?}: {}", &cm.pak_path(), err)
-                        });
+                        for pak_entry in cm.chosen_paks() {
+                            files.push((mod_type, &cm.source, pak_entry));
+                        }
                     }
                 }
                 MultiPak(mp) => {
                     if mp.enabled {
-                        let destination_path = game_mod_directory.join(&mp.selected_pak().pak);
-                        fs::copy(&mp.selected_pak().path, &destination_path).unwrap_or_else(
-                            |err| panic!("Failed to copy {:?}: {}", &mp.selected_pak().path, err),
-                        );
+                        for pak in mp.chosen_paks() {
+                            files.push((mod_type, &mp.source, Pak::entry(&pak.pak)));
+                        }
                     }
                 }
             }
         }
+        // Mods can ship paks with the same name, which would overwrite each other in ~mods
+        let mut deployment = Deployment::default();
+        let mut deployments: Vec<(&ModSource, PathBuf, PathBuf)> = Vec::new();
+        for (mod_type, source, entry) in files {
+            let deployed_name = deployment.add(mod_type.name(), source, &entry);
+            deployments.push((source, entry, game_mod_directory.join(deployed_name)));
+        }
+        let required = deployments
+            .iter()
+            .map(|(source, entry, _)| {
+                source.size(entry).unwrap_or_else(|err| {
+                    eprintln!("{}", err);
+                    0
+                })
+            })
+            .sum();
+        // Whatever was deployed before gets removed first
+        let previous = Deployment::load(&game_mod_directory);
+        let freed = match &previous {
+            Some(previous) => previous.size_on_disk(&game_mod_directory),
+            None => archive::size_on_disk(&game_mod_directory),
+        };
+        archive::check_free_space(&game_mod_directory, required, freed)
+            .map_err(LoadError::NoSpace)?;
+
+        self.clear_mod_directory();
+        let verify = self.settings.verify_deployment;
+        let mut report = DeploymentReport::default();
+        let mut copied: Vec<PathBuf> = Vec::new();
+        let deployed = fs::create_dir_all(&game_mod_directory)
+            .map_err(|err| format!("Failed to create {:?}: {}", game_mod_directory, err))
+            .and_then(|_| {
+                for (source, entry, destination_path) in deployments {
+                    // Added first so a half written copy is removed with the rest
+                    copied.push(destination_path.clone());
+                    deploy_file(source, &entry, &destination_path, verify, &mut report)?;
+                }
+                Ok(())
+            });
+        if let Err(err) = deployed {
+            eprintln!("Failed to load mods: {}", err);
+            let message = Self::roll_back(&game_mod_directory, &copied, previous, &err);
+            self.deployment = Deployment::load(&game_mod_directory).unwrap_or_default();
+            return Err(LoadError::Deploy(message));
+        }
+        if verify {
+            println!("{}", report.summary());
+        }
+        for file in deployment.renamed() {
+            println!(
+                "{} from {} deployed as {}",
+                file.file_name, file.mod_name, file.deployed_name
+            );
+        }
+        deployment
+            .record_files(&game_mod_directory)
+            .and_then(|_| deployment.save(&game_mod_directory))
+            .unwrap_or_else(|err| eprintln!("{}", err));
+        self.deployment = deployment;
+        self.drift.clear();
+        self.deployment_report = verify.then_some(report);
+        Ok(())
+    }
+    // Shows how Load Mods went, for the button and for re-syncing alike
+    fn set_load_status(&mut self, loaded: Result<(), LoadError>) {
+        self.mod_load_status = match loaded {
+            Ok(()) => "success",
+            Err(LoadError::InvalidGameDirectory) => "failed",
+            Err(LoadError::Verifying) => "verifying",
+            Err(LoadError::Unresolved) => "unresolved",
+            Err(err) => {
+                self.load_error = Some(err);
+                "load_error"
+            }
+        }
+        .to_string();
+    }
+    // Takes a failed load back out of ~mods and copies the mods deployed before it in again,
+    // returning what to tell the user
+    fn roll_back(
+        game_mod_directory: &Path,
+        copied: &[PathBuf],
+        previous: Option<Deployment>,
+        err: &str,
+    ) -> String {
+        for path in copied {
+            if path.exists() {
+                fs::remove_file(path)
+                    .unwrap_or_else(|err| eprintln!("Failed to remove {:?}: {}", path, err));
+            }
+        }
+        let Some(mut previous) = previous else {
+            return format!("{}. The files copied so far were removed again.", err);
+        };
+        let failed = previous.restore(game_mod_directory, false, &mut DeploymentReport::default());
+        if failed.is_empty() {
+            format!("{}. The mods loaded before were put back.", err)
+        } else {
+            format!(
+                "{}. Putting the mods loaded before back failed for {}.",
+                err,
+                failed.join(", ")
+            )
+        }
+    }
+    // Compares ~mods against what was last deployed, if mods were ever loaded
+    pub fn check_drift(&mut self) {
+        let game_mod_directory = self
+            .game_directory
+            .join(r"MarvelGame\Marvel\Content\Paks\~mods");
+        self.drift = match Deployment::load(&game_mod_directory) {
+            Some(deployment) => {
+                let drift = deployment.check_drift(&game_mod_directory);
+                self.deployment = deployment;
+                drift
+            }
+            None => Vec::new(),
+        };
+        for drift in &self.drift {
+            println!("~mods changed: {}", drift);
+        }
+        self.show_drift = !self.drift.is_empty();
+        // Files the user put in ~mods themselves don't mean the loaded mods changed
+        let deployed_changed = self
+            .drift
+            .iter()
+            .any(|drift| !matches!(drift, Drift::Extra { .. }));
+        if deployed_changed && self.mod_load_status == "success" {
+            self.mod_load_status = "drift".to_string();
+        }
+    }
+    // Puts ~mods back the way the last load left it, copying each recorded file again from its
+    // mod. Mods enabled in the list are a selection made since, so those are loaded instead.
+    // Files something else added are left alone, they are only removed when asked to.
+    fn resync(&mut self) {
+        self.deployment_report = None;
+        self.restore_errors.clear();
+        let restored = if self.modifications.iter().any(ModType::is_enabled) {
+            // Held to the same checks as the Load Mods button
+            self.load_mods(self.ignore_dependency_problems)
+        } else {
+            self.restore_deployment()
+        };
+        if restored.is_ok() && !self.restore_errors.is_empty() {
+            self.mod_load_status = "restore_failed".to_string();
+        } else {
+            self.set_load_status(restored);
+        }
+        self.check_drift();
+    }
+    fn restore_deployment(&mut self) -> Result<(), LoadError> {
+        if !self.validate_game_directory() {
+            return Err(LoadError::InvalidGameDirectory);
+        }
+        let game_mod_directory = self
+            .game_directory
+            .join(r"MarvelGame\Marvel\Content\Paks\~mods");
+        // Every recorded file is written again, replacing what's there
+        archive::check_free_space(
+            &game_mod_directory,
+            self.deployment.size(),
+            self.deployment.size_on_disk(&game_mod_directory),
+        )
+        .map_err(LoadError::NoSpace)?;
+        let verify = self.settings.verify_deployment;
+        let mut report = DeploymentReport::default();
+        self.restore_errors = self
+            .deployment
+            .restore(&game_mod_directory, verify, &mut report);
+        for err in &self.restore_errors {
+            eprintln!("Failed to restore {}", err);
+        }
+        self.deployment_report = verify.then_some(report);
+        Ok(())
+    }
+    // Deletes what the drift check found in ~mods that the manager didn't put there
+    fn remove_extra_files(&mut self) {
+        let game_mod_directory = self
+            .game_directory
+            .join(r"MarvelGame\Marvel\Content\Paks\~mods");
+        for drift in &self.drift {
+            if let Drift::Extra { file } = drift {
+                let path = game_mod_directory.join(file);
+                let removed = if path.is_dir() {
+                    fs::remove_dir_all(&path)
+                } else {
+                    fs::remove_file(&path)
+                };
+                removed.unwrap_or_else(|err| eprintln!("Failed to remove {:?}: {}", path, err));
+            }
+        }
+        self.check_drift();
+    }
+    fn show_drift_window(&mut self, ctx: &Context) {
+        if !self.show_drift {
+            return;
+        }
+        let mut resync = false;
+        let mut remove_extra = false;
+        let mut launch = false;
+        let mut dismiss = false;
+        let extra_files = self
+            .drift
+            .iter()
+            .filter(|drift| matches!(drift, Drift::Extra { .. }))
+            .count();
+        egui::Window::new("~mods Changed")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("The game's ~mods folder no longer matches the mods that were loaded:");
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for drift in &self.drift {
+                            ui.label(drift.to_string());
+                        }
+                    });
+                if self.confirm_remove_extra {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Delete the {} files in ~mods the manager didn't put there?",
+                            extra_files
+                        ))
+                        .color(egui::Color32::YELLOW),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            remove_extra = true;
+                        }
+                        if ui.button("Keep").clicked() {
+                            self.confirm_remove_extra = false;
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Re-sync")
+                        .on_hover_text("Copies the loaded mods into ~mods again")
+                        .clicked()
+                    {
+                        resync = true;
+                    }
+                    if extra_files > 0 && ui.button("Remove Extra Files").clicked() {
+                        self.confirm_remove_extra = true;
+                    }
+                    if self.launch_pending && ui.button("Launch Anyway").clicked() {
+                        launch = true;
+                    }
+                    if ui.button("Ignore").clicked() {
+                        dismiss = true;
+                    }
+                });
+            });
+        if resync {
+            self.resync();
+            // Files the user added are theirs to keep, only the deployed ones have to match
+            let restored = self
+                .drift
+                .iter()
+                .all(|drift| matches!(drift, Drift::Extra { .. }));
+            if self.launch_pending && restored {
+                self.show_drift = false;
+                self.launch_pending = false;
+                self.launch();
+            }
+        } else if remove_extra {
+            self.confirm_remove_extra = false;
+            self.remove_extra_files();
+        } else if launch {
+            self.show_drift = false;
+            self.launch_pending = false;
+            self.launch();
+        } else if dismiss {
+            self.show_drift = false;
+            self.confirm_remove_extra = false;
+            self.launch_pending = false;
+        }
     }
     pub fn init_mods(&mut self) {
+        self.refresh_game_build();
+        self.check_drift();
         self.modifications.clear();
+        self.selected_mod_index = None;
+        self.textures.clear();
+        self.password_requests.clear();
+        self.unsafe_archive_reports.clear();
+        self.disk_space_errors.clear();
+        self.install_errors.clear();
+        self.fomod_installers.clear();
         if let Ok(entries) = std::fs::read_dir(&self.mod_directory) {
             for entry in entries {
                 match entry {
                     Ok(entry) => {
                         let path = entry.path();
-                        if path.to_str().unwrap().ends_with(".7z") {
-                            println!("Extracting 7z file {}", path.to_str().unwrap());
-                            if let Err(err) = Self::extract_archive(&path) {
-                                eprintln!("Failed to extract archive: {}", err);
-                            }
-                        } else if path.to_str().unwrap().ends_with(".zip") {
-                            println!("Extracting zip file {}", path.to_str().unwrap());
-                            if let Err(err) = Self::extract_archive(&path) {
-                                eprintln!("Failed to extract archive: {}", err);
-                            }
+                        if entry.file_name().to_string_lossy().starts_with('.') {
+                            // Hidden files, including in-progress extraction folders
+                            continue;
                         }
-                        if path.is_dir() {
-                            if path.join("paks").exists() {
-                                println!("Building MultiPak from {}", path.to_str().unwrap());
-                                match MultiPak::build(path) {
-                                    Ok(multipak) => {
-                                        self.modifications.push(ModType::MultiPak(multipak))
-                                    } // Push the MultiPak if build succeeds
-                                    Err(err) => eprintln!("Failed to build MultiPak: {}", err), // Log the error if it fails
-                                }
-                            } else {
-                                println!("Building Complete mod from {}", path.to_str().unwrap());
-                                match Modification::build(path) {
-                                    Ok(modification) => {
-                                        self.modifications.push(ModType::Complete(modification))
-                                    } // Build a complete mod if successful
-                                    Err(err) => eprintln!("Failed to build modification: {}", err), // Log the error if it fails
-                                }
-                            }
-                        } else if path.to_str().unwrap().ends_with(".pak") {
-                            println!("Building LoosePak from {}", path.to_str().unwrap());
-                            match LoosePak::build(path) {
-                                Ok(pak) => self.modifications.push(ModType::LoosePak(pak)),
-                                Err(err) => eprintln!("Failed to build Pak: {}", err),
-                            }
+                        if archive::is_secondary_volume(&path) {
+                            // Joined back together when the first volume is extracted
+                            continue;
+                        }
+                        if self.settings.read_archives_in_place
+                            && archive::is_archive(&path)
+                            && !Self::needs_extraction(&path)
+                        {
+                            println!("Reading archive {}", path.to_str().unwrap());
+                            self.add_archive_mods(path);
+                            continue;
+                        }
+                        if archive::is_archive(&path) && archive::is_extracted(&path) {
+                            // What it was extracted to is in the mod directory too
+                            continue;
+                        }
+                        if archive::is_archive(&path) {
+                            self.queue_extraction(path);
                         } else {
-                            println!("{:?} is not a mod file type.", path)
+                            self.add_mod_path(path);
                         }
                     }
                     Err(err) => {
@@ -156,6 +630,240 @@ impl ModManager {
             eprintln!("Failed to read mod directory {:?}", self.mod_directory)
         }
     }
+    // Builds the mod for a folder or pak in the mod directory, replacing it if already listed
+    fn add_mod_path(&mut self, path: PathBuf) {
+        // Rewriting a signed manifest would break its signature
+        if self.settings.rewrite_upgraded_manifests
+            && path.join("mod.json").is_file()
+            && !path.join(SIGNATURE_FILE).exists()
+        {
+            match manifest::upgrade_on_disk(&path) {
+                Ok(Some(version)) => println!(
+                    "Upgraded {:?} from format version {}",
+                    path.join("mod.json"),
+                    version
+                ),
+                Ok(None) => {}
+                Err(err) => eprintln!("Failed to upgrade manifest: {}", err),
+            }
+        }
+        let built = if path.is_dir() {
+            if path.join("paks").exists() {
+                println!("Building MultiPak from {}", path.to_str().unwrap());
+                MultiPak::build(path.clone())
+                    .map(ModType::MultiPak)
+                    .map_err(|err| format!("Failed to build MultiPak: {}", err))
+            } else if manifest::find(&ModSource::Directory(path.clone())).is_none()
+                && FomodInstaller::find_config(&ModSource::Directory(path.clone())).is_some()
+            {
+                self.remove_mods(|modification| modification.path() != &path);
+                self.queue_fomod(ModSource::Directory(path));
+                return;
+            } else {
+                println!("Building Complete mod from {}", path.to_str().unwrap());
+                Modification::build(path.clone())
+                    .map(ModType::Complete)
+                    .map_err(|err| format!("Failed to build modification: {}", err))
+            }
+        } else if path.to_str().unwrap().ends_with(".pak") {
+            println!("Building LoosePak from {}", path.to_str().unwrap());
+            LoosePak::build(path.clone())
+                .map(ModType::LoosePak)
+                .map_err(|err| format!("Failed to build Pak: {}", err))
+        } else {
+            println!("{:?} is not a mod file type.", path);
+            return;
+        };
+        match built {
+            Ok(modification) => self.push_mod(modification),
+            Err(err) => {
+                eprintln!("{}", err);
+                // A mod listed for this path before doesn't build anymore
+                self.remove_mods(|modification| modification.path() != &path);
+            }
+        }
+    }
+    // Hands an archive to the background extractors, using any password or approval the
+    // user already gave for it
+    fn queue_extraction(&mut self, path: PathBuf) {
+        let mut job = archive::ExtractJob::new(path.clone());
+        job.password = self.archive_passwords.get(&path).cloned();
+        job.unsafe_file_policy = self.settings.unsafe_file_policy;
+        job.allow_unsafe = self.approved_unsafe_archives.contains(&path);
+        if self.extraction_pool.submit(job) {
+            println!("Extracting archive {}", path.to_str().unwrap());
+        }
+    }
+    // Adds the mods from every archive that finished extracting since the last frame
+    fn poll_extractions(&mut self) {
+        for outcome in self.extraction_pool.poll() {
+            match outcome.result {
+                Ok(extracted) => {
+                    for path in extracted {
+                        self.add_mod_path(path);
+                    }
+                }
+                Err(archive::ExtractFailure::Password(password_error)) => self
+                    .password_requests
+                    .push((password_error.path().clone(), password_error.to_string())),
+                Err(archive::ExtractFailure::UnsafeContent(unsafe_content)) => {
+                    self.unsafe_archive_reports.push(unsafe_content)
+                }
+                Err(archive::ExtractFailure::NoSpace(no_space)) => {
+                    eprintln!("Failed to extract archive {:?}: {}", outcome.path, no_space);
+                    self.disk_space_errors.push(no_space)
+                }
+                Err(archive::ExtractFailure::Cancelled) => {
+                    println!("Cancelled extracting {:?}", outcome.path)
+                }
+                Err(archive::ExtractFailure::Failed(err)) => {
+                    eprintln!("Failed to extract archive {:?}: {}", outcome.path, err)
+                }
+            }
+        }
+    }
+    fn show_extraction_progress(&self, ui: &mut egui::Ui) {
+        if self.extraction_pool.active().is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.heading("Extracting Archives");
+        for (path, progress) in self.extraction_pool.active() {
+            ui.horizontal(|ui| {
+                ui.label(path.file_name().unwrap_or_default().to_string_lossy());
+                let megabytes =
+                    |bytes: &AtomicU64| bytes.load(Ordering::Relaxed) as f64 / (1024.0 * 1024.0);
+                ui.add(
+                    egui::ProgressBar::new(progress.fraction())
+                        .desired_width(250.0)
+                        .text(format!(
+                            "{:.1} / {:.1} MB, {} / {} files",
+                            megabytes(&progress.bytes_written),
+                            megabytes(&progress.total_bytes),
+                            progress.entries_written.load(Ordering::Relaxed),
+                            progress.total_entries.load(Ordering::Relaxed),
+                        )),
+                );
+                if progress.is_cancelled() {
+                    ui.label("Cancelling...");
+                } else if ui.button("Cancel").clicked() {
+                    progress.cancel();
+                }
+            });
+        }
+        // Keep redrawing so the bars move without any input
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+    // Split and encrypted archives can't be read in place, so they are always extracted
+    fn needs_extraction(path: &Path) -> bool {
+        archive::is_volume(path) || archive::is_encrypted(path).unwrap_or(false)
+    }
+    // Builds mods straight from an archive without extracting it
+    fn add_archive_mods(&mut self, path: PathBuf) {
+        let source = match ModSource::open_archive(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("{}", err);
+                return;
+            }
+        };
+        if manifest::find(&source).is_some() {
+            if source.is_dir(Path::new("paks")) {
+                match MultiPak::from_source(source) {
+                    Ok(multipak) => self.push_mod(ModType::MultiPak(multipak)),
+                    Err(err) => eprintln!("Failed to build MultiPak: {}", err),
+                }
+            } else {
+                match Modification::from_source(source) {
+                    Ok(modification) => self.push_mod(ModType::Complete(modification)),
+                    Err(err) => eprintln!("Failed to build modification: {}", err),
+                }
+            }
+        } else if FomodInstaller::find_config(&source).is_some() {
+            self.queue_fomod(source);
+        } else {
+            for entry in source.list(Path::new("")) {
+                if entry.to_str().unwrap().ends_with(".pak") {
+                    match LoosePak::from_source(source.clone(), entry) {
+                        Ok(pak) => self.push_mod(ModType::LoosePak(pak)),
+                        Err(err) => eprintln!("Failed to build Pak: {}", err),
+                    }
+                }
+            }
+        }
+    }
+    // Makes the enabled mods consistent again after the user changed them. `toggled` is the
+    // mod they just switched on or off, which wins over the ones that were enabled before.
+    fn resolve_enabled(&mut self, toggled: Option<usize>) {
+        let switched_on = toggled.filter(|&index| self.modifications[index].is_enabled());
+        let required: Vec<usize> = switched_on.into_iter().collect();
+        let rejected: Vec<usize> = toggled
+            .filter(|_| switched_on.is_none())
+            .into_iter()
+            .collect();
+        let preferred: Vec<usize> = (0..self.modifications.len())
+            .filter(|&index| self.modifications[index].is_enabled() && switched_on != Some(index))
+            .collect();
+        self.resolution_notes.clear();
+        match resolve_selection(&self.modifications, &required, &preferred, &rejected) {
+            Ok(resolution) => {
+                for (index, modification) in self.modifications.iter_mut().enumerate() {
+                    modification.set_enabled(resolution.selection.contains(&index));
+                }
+                for (index, reason) in resolution.added {
+                    self.resolution_notes.push(format!(
+                        "Enabled {} ({})",
+                        self.modifications[index].name(),
+                        reason
+                    ));
+                }
+                for (index, reason) in resolution.removed {
+                    self.resolution_notes.push(format!(
+                        "Disabled {} ({})",
+                        self.modifications[index].name(),
+                        reason
+                    ));
+                }
+            }
+            // Only a mod the user switched on can be impossible to enable
+            Err(unsatisfiable) => {
+                let index = switched_on.unwrap();
+                self.modifications[index].set_enabled(false);
+                self.resolution_notes.push(format!(
+                    "Can't enable {}: {}",
+                    self.modifications[index].name(),
+                    unsatisfiable
+                ));
+            }
+        }
+    }
+    fn queue_fomod(&mut self, source: ModSource) {
+        // Once installed, the mod is picked up from its own folder instead
+        let output_dir = ModSource::Directory(Self::fomod_output_dir(&source));
+        if manifest::find(&output_dir).is_some() {
+            return;
+        }
+        match FomodInstaller::open(source) {
+            Ok(installer) => {
+                println!("Found FOMOD installer for {}", installer.name());
+                self.fomod_installers
+                    .retain(|queued| queued.source.path() != installer.source.path());
+                self.fomod_installers.push(installer);
+            }
+            Err(err) => eprintln!("Failed to read FOMOD installer: {}", err),
+        }
+    }
+    // Installs go to a folder next to the installer, so options it didn't pick can't end up
+    // being loaded with the mod
+    fn fomod_output_dir(source: &ModSource) -> PathBuf {
+        let path = source.path();
+        let name = match source {
+            ModSource::Directory(_) => path.file_name().unwrap_or_default().to_string_lossy(),
+            ModSource::Archive { .. } => path.file_stem().unwrap_or_default().to_string_lossy(),
+        };
+        let name = name.strip_suffix(".tar").unwrap_or(&name);
+        path.with_file_name(format!("{} (installed)", name))
+    }
     fn add_mod(&mut self, file_path: &PathBuf) -> Result<(), std::io::Error> {
         // Ensure the target directory exists
         fs::create_dir_all(&self.mod_directory)?;
@@ -175,14 +883,41 @@ impl ModManager {
 
         Ok(())
     }
-    fn load_image_to_texture(ctx: &egui::Context, path: &std::path::Path) -> Option<TextureHandle> {
-        if !path.exists() {
+    // Decoding is only done once per image, reading it again from an archive every frame would
+    // decompress the entry each time. Images that fail to load are remembered too.
+    fn load_image_to_texture(
+        ctx: &egui::Context,
+        textures: &mut TextureCache,
+        source: &ModSource,
+        image_path: &Path,
+    ) -> Option<TextureHandle> {
+        let path = source.display_path(image_path);
+        textures
+            .0
+            .entry(path)
+            .or_insert_with_key(|path| Self::decode_texture(ctx, source, image_path, path))
+            .clone()
+    }
+    fn decode_texture(
+        ctx: &egui::Context,
+        source: &ModSource,
+        image_path: &Path,
+        path: &Path,
+    ) -> Option<TextureHandle> {
+        if !source.exists(image_path) {
             eprintln!("Image path does not exist: {:?}", path);
             return None;
         }
+        let bytes = match source.read_bytes(image_path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("{}", err);
+                return None;
+            }
+        };
 
         // Load image dynamically at runtime
-        match image::open(&path) {
+        match image::load_from_memory(&bytes) {
             Ok(img) => {
                 let (width, height) = img.dimensions();
                 let rgba_image = img.to_rgba8();
@@ -201,61 +936,564 @@ impl ModManager {
             }
         }
     }
+    // The optional details of a mod, skipping whatever the author left out
+    fn show_metadata(
+        ctx: &Context,
+        ui: &mut egui::Ui,
+        textures: &mut TextureCache,
+        metadata: &Metadata,
+        source: &ModSource,
+    ) {
+        if let Some(cover) = &metadata.cover {
+            if let Some(texture) = Self::load_image_to_texture(ctx, textures, source, cover) {
+                ui.add(
+                    egui::Image::new(&texture)
+                        .max_width(ui.available_width())
+                        .max_height(160.0),
+                );
+            }
+        }
+        if !metadata.tags.is_empty() {
+            ui.label(format!("Tags: {}", metadata.tags.join(", ")));
+        }
+        if metadata.links().next().is_some() {
+            ui.horizontal(|ui| {
+                for (label, url) in metadata.links() {
+                    ui.hyperlink_to(label, url);
+                }
+            });
+        }
+        if !metadata.license.is_empty() {
+            ui.label(format!("License: {}", metadata.license));
+        }
+        if !metadata.redistribution.is_unspecified() {
+            ui.label(format!(
+                "Redistribution: {}",
+                metadata.redistribution.label()
+            ));
+        }
+        if !metadata.contributors.is_empty() {
+            let contributors: Vec<String> = metadata
+                .contributors
+                .iter()
+                .map(|contributor| match contributor.role.as_str() {
+                    "" => contributor.name.clone(),
+                    role => format!("{} ({})", contributor.name, role),
+                })
+                .collect();
+            ui.label(format!("Contributors: {}", contributors.join(", ")));
+        }
+        if !metadata.updated.is_empty() {
+            ui.label(format!("Last updated: {}", metadata.updated));
+        }
+    }
     pub fn extract_archive(file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        // Ensure the provided file path exists and is a file
-        if !file_path.exists() || !file_path.is_file() {
-            return Err("Provided file_path does not exist or is not a valid file.".into());
+        archive::extract_archive(file_path)
+    }
+    fn show_password_prompt(&mut self, ctx: &Context) {
+        let Some((archive_path, reason)) = self.password_requests.first().cloned() else {
+            return;
+        };
+        let mut unlock = false;
+        let mut cancel = false;
+        egui::Window::new("Password Required")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Enter the password for {}",
+                    archive_path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ));
+                ui.label(egui::RichText::new(reason).color(egui::Color32::RED));
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut self.password_input).password(true));
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    unlock = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Unlock").clicked() {
+                        unlock = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        if unlock {
+            let password = std::mem::take(&mut self.password_input);
+            self.archive_passwords
+                .insert(archive_path.clone(), password);
+            self.password_requests.remove(0);
+            self.queue_extraction(archive_path);
+        } else if cancel {
+            self.password_input.clear();
+            self.password_requests.remove(0);
         }
-
-        // Get the parent directory and stem name for output
-        let parent_dir = file_path
-            .parent()
-            .ok_or("Unable to determine parent directory.")?;
-        let output_dir = parent_dir;
-
-        // Create the output directory if it doesn't exist
-        if !output_dir.exists() {
-            create_dir_all(&output_dir)?;
+    }
+    fn show_unsafe_content_warning(&mut self, ctx: &Context) {
+        let Some(report) = self.unsafe_archive_reports.first() else {
+            return;
+        };
+        let mut extract = false;
+        let mut dismiss = false;
+        egui::Window::new("Unsafe Files Found")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} contains files that don't belong in a mod:",
+                    report
+                        .path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for entry in &report.flagged {
+                            ui.label(format!("{} ({})", entry.name, entry.reason));
+                        }
+                    });
+                if report.blocked {
+                    ui.label(
+                        egui::RichText::new(
+                            "Extraction was blocked. This can be changed in Settings.",
+                        )
+                        .color(egui::Color32::RED),
+                    );
+                    if ui.button("OK").clicked() {
+                        dismiss = true;
+                    }
+                } else {
+                    ui.horizontal(|ui| {
+                        if ui.button("Extract Anyway").clicked() {
+                            extract = true;
+                        }
+                        if ui.button("Skip").clicked() {
+                            dismiss = true;
+                        }
+                    });
+                }
+            });
+        if extract {
+            let report = self.unsafe_archive_reports.remove(0);
+            self.approved_unsafe_archives.insert(report.path.clone());
+            self.queue_extraction(report.path);
+        } else if dismiss {
+            self.unsafe_archive_reports.remove(0);
         }
-
-        // Match based on file extension
-        match file_path.extension().and_then(|ext| ext.to_str()) {
-            Some("zip") => {
-                let file = std::fs::File::open(file_path)?;
-                let mut archive = ZipArchive::new(file)?;
-
-                for i in 0..archive.len() {
-                    let mut file = archive.by_index(i)?;
-                    let out_path = output_dir.join(file.name());
-
-                    if (&*file.name()).ends_with('/') {
-                        // Directory entry
-                        create_dir_all(&out_path)?;
-                    } else {
-                        // File entry
-                        if let Some(parent) = out_path.parent() {
-                            create_dir_all(parent)?;
+    }
+    fn show_fomod_wizard(&mut self, ctx: &Context) {
+        let Some(installer) = self.fomod_installers.first() else {
+            return;
+        };
+        let step = installer.current_step().cloned();
+        let mut changes = Vec::new();
+        let mut back = false;
+        let mut next = false;
+        let mut install = false;
+        let mut cancel = false;
+        egui::Window::new(format!("Install {}", installer.name()))
+            .id(egui::Id::new("fomod_wizard"))
+            .collapsible(false)
+            .show(ctx, |ui| {
+                match &step {
+                    Some(step) => {
+                        ui.heading(&step.name);
+                        egui::ScrollArea::vertical()
+                            .max_height(350.0)
+                            .show(ui, |ui| {
+                                for (group_index, group) in
+                                    step.optional_file_groups.group.iter().enumerate()
+                                {
+                                    ui.separator();
+                                    ui.label(egui::RichText::new(&group.name).strong());
+                                    for (plugin_index, plugin) in
+                                        group.plugins.plugin.iter().enumerate()
+                                    {
+                                        let mut selected =
+                                            installer.is_selected(group_index, plugin_index);
+                                        let locked = installer.is_locked(group_index, plugin_index);
+                                        let response = ui
+                                            .add_enabled_ui(!locked, |ui| match group.group_type {
+                                                GroupType::SelectExactlyOne
+                                                | GroupType::SelectAtMostOne => {
+                                                    ui.radio(selected, &plugin.name)
+                                                }
+                                                _ => ui.checkbox(&mut selected, &plugin.name),
+                                            })
+                                            .inner;
+                                        if response.clicked() {
+                                            let selected = match group.group_type {
+                                                GroupType::SelectExactlyOne => true,
+                                                // Clicking the picked option again clears the group
+                                                GroupType::SelectAtMostOne => !selected,
+                                                _ => selected,
+                                            };
+                                            changes.push((group_index, plugin_index, selected));
+                                        }
+                                        if response.contains_pointer() {
+                                            self.fomod_focus = Some((group_index, plugin_index));
+                                        }
+                                    }
+                                }
+                            });
+                        // Details of whichever option was pointed at last
+                        let focused = self.fomod_focus.and_then(|(group, plugin)| {
+                            step.optional_file_groups
+                                .group
+                                .get(group)?
+                                .plugins
+                                .plugin
+                                .get(plugin)
+                        });
+                        if let Some(plugin) = focused {
+                            ui.separator();
+                            ui.label(plugin.description.trim());
+                            if let Some(image) = plugin
+                                .image
+                                .as_ref()
+                                .and_then(|image| installer.resolve(&image.path))
+                            {
+                                if let Some(texture) = Self::load_image_to_texture(
+                                    ctx,
+                                    &mut self.textures,
+                                    &installer.source,
+                                    image,
+                                ) {
+                                    ui.add(
+                                        egui::Image::new(&texture)
+                                            .max_width(400.0)
+                                            .max_height(250.0),
+                                    );
+                                }
+                            }
                         }
-                        let mut out_file = std::fs::File::create(out_path)?;
-                        std::io::copy(&mut file, &mut out_file)?;
+                    }
+                    None => {
+                        ui.label("There are no options to choose, the mod is ready to install.");
                     }
                 }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(installer.has_previous(), egui::Button::new("Back"))
+                        .clicked()
+                    {
+                        back = true;
+                    }
+                    let complete = installer.is_step_complete();
+                    if installer.is_last_step() {
+                        install = ui
+                            .add_enabled(complete, egui::Button::new("Install"))
+                            .clicked();
+                    } else {
+                        next = ui
+                            .add_enabled(complete, egui::Button::new("Next"))
+                            .clicked();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        let installer = &mut self.fomod_installers[0];
+        for (group, plugin, selected) in changes {
+            installer.set_selected(group, plugin, selected);
+        }
+        if back || next {
+            self.fomod_focus = None;
+            if back {
+                installer.back();
+            } else {
+                installer.next();
             }
-            Some("7z") => {
-                decompress_7z(file_path, &output_dir)?;
+        } else if install {
+            let installer = self.fomod_installers.remove(0);
+            let output_dir = Self::fomod_output_dir(&installer.source);
+            println!("Installing {} into {:?}", installer.name(), output_dir);
+            match installer.install(&output_dir) {
+                Ok(modification) => self.push_mod(ModType::Complete(modification)),
+                Err(err) => {
+                    eprintln!("Failed to install {}: {}", installer.name(), err);
+                    self.install_errors.push(format!(
+                        "Failed to install {}: {}",
+                        installer.name(),
+                        err
+                    ));
+                }
             }
-            _ => {
-                return Err("Unsupported file format. Only .zip and .7z are supported.".into());
+            self.fomod_focus = None;
+        } else if cancel {
+            self.fomod_installers.remove(0);
+            self.fomod_focus = None;
+        }
+    }
+    fn show_settings_window(&mut self, ctx: &Context) {
+        let mut changed = false;
+        let mut reload = false;
+        let mut keyring_changed = false;
+        egui::Window::new("Settings")
+            .open(&mut self.show_settings)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if ui
+                    .checkbox(
+                        &mut self.settings.read_archives_in_place,
+                        "Read archives without extracting",
+                    )
+                    .changed()
+                {
+                    changed = true;
+                    reload = true;
+                }
+                if ui
+                    .checkbox(
+                        &mut self.settings.rewrite_upgraded_manifests,
+                        "Save old mod.json files in the current format (keeps a backup)",
+                    )
+                    .changed()
+                {
+                    changed = true;
+                    reload = self.settings.rewrite_upgraded_manifests;
+                }
+                ui.separator();
+                ui.label("Archives containing executables, scripts, DLLs or shortcuts:");
+                changed |= ui
+                    .radio_value(
+                        &mut self.settings.unsafe_file_policy,
+                        archive::UnsafeFilePolicy::Warn,
+                        "Warn before extracting",
+                    )
+                    .changed();
+                changed |= ui
+                    .radio_value(
+                        &mut self.settings.unsafe_file_policy,
+                        archive::UnsafeFilePolicy::Block,
+                        "Block extraction",
+                    )
+                    .changed();
+                changed |= ui
+                    .checkbox(
+                        &mut self.settings.verify_deployment,
+                        "Check paks copied into ~mods against the originals",
+                    )
+                    .changed();
+                if ui
+                    .checkbox(
+                        &mut self.settings.verify_on_scan,
+                        "Check mod files against their hashes when scanning",
+                    )
+                    .changed()
+                {
+                    changed = true;
+                    reload = self.settings.verify_on_scan;
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Language for mod names and descriptions:");
+                    let system = sys_locale::get_locale().unwrap_or_default();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.settings.language)
+                                .hint_text(format!("{} (system)", system)),
+                        )
+                        .changed();
+                });
+                ui.separator();
+                ui.label("Trusted authors, whose signed mods are shown as verified:");
+                let mut removed = None;
+                for (index, author) in self.keyring.authors.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(&author.name).on_hover_text(&author.public_key);
+                        if ui.button("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                }
+                if let Some(index) = removed {
+                    self.keyring.authors.remove(index);
+                    keyring_changed = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_author_name)
+                            .hint_text("Name")
+                            .desired_width(100.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_author_key)
+                            .hint_text("Public key"),
+                    );
+                    if ui.button("Trust").clicked() {
+                        match self
+                            .keyring
+                            .trust(&self.new_author_name, &self.new_author_key)
+                        {
+                            Ok(()) => {
+                                self.new_author_name.clear();
+                                self.new_author_key.clear();
+                                keyring_changed = true;
+                            }
+                            Err(err) => eprintln!("{}", err),
+                        }
+                    }
+                });
+            });
+        if changed {
+            self.settings.save(&self.settings_path);
+        }
+        if keyring_changed {
+            self.keyring.save(&self.keyring_path);
+        }
+        if reload {
+            self.init_mods();
+        }
+    }
+    // Enabled mods with unmet requirements, conflicts, corrupted files or that don't work with
+    // this game build
+    fn has_unresolved_problems(&self) -> bool {
+        !self.dependency_problems.is_empty()
+            || self
+                .incompatibilities
+                .iter()
+                .any(|(index, _)| self.modifications[*index].is_enabled())
+            || self.modifications.iter().any(|modification| {
+                modification.is_enabled() && !modification.integrity_problems().is_empty()
+            })
+    }
+    // Marks a mod in the list that won't work with the installed game build or this manager
+    fn show_incompatibility(
+        ui: &mut egui::Ui,
+        incompatibilities: &[(usize, Incompatibility)],
+        index: usize,
+    ) {
+        let problems: Vec<String> = incompatibilities
+            .iter()
+            .filter(|(other, _)| *other == index)
+            .map(|(_, problem)| problem.to_string())
+            .collect();
+        if !problems.is_empty() {
+            ui.label(egui::RichText::new("⚠").color(egui::Color32::RED))
+                .on_hover_text(problems.join("\n"));
+        }
+    }
+    // Shows in the list whether a mod is signed by someone trusted. Until its files have been
+    // checked a signature can only be taken back, so it isn't shown as verified yet.
+    fn show_signature_status(ui: &mut egui::Ui, status: &SignatureStatus, verifying: bool) {
+        let (text, color, hover) = match status {
+            SignatureStatus::Verified { author } if verifying => (
+                "checking",
+                egui::Color32::GRAY,
+                format!("Signed by {}, checking the files", author),
+            ),
+            SignatureStatus::Verified { author } => (
+                "verified",
+                egui::Color32::GREEN,
+                format!("Signed by {}\n{}", author, SIGNATURE_COVERAGE),
+            ),
+            SignatureStatus::Unknown { signed_by: None } => {
+                ("unknown", egui::Color32::GRAY, "Not signed".to_string())
+            }
+            SignatureStatus::Unknown {
+                signed_by: Some(author),
+            } => (
+                "unknown",
+                egui::Color32::GRAY,
+                format!("Signed as {}, who isn't a trusted author", author),
+            ),
+            SignatureStatus::Invalid { reason } => (
+                "invalid",
+                egui::Color32::RED,
+                format!("Bad signature: {}", reason),
+            ),
+        };
+        ui.label(egui::RichText::new(text).small().color(color))
+            .on_hover_text(hover);
+    }
+    // The signature in a mod's details, letting whoever signed it be trusted
+    fn show_signature(
+        ui: &mut egui::Ui,
+        keyring: &mut Keyring,
+        keyring_path: &Path,
+        signature: &SignatureCheck,
+        integrity_problems: &[IntegrityProblem],
+        verifying: bool,
+    ) {
+        match keyring.status(signature, integrity_problems) {
+            SignatureStatus::Verified { author } if verifying => {
+                ui.label(format!(
+                    "Signature: signed by {}, checking the files still match",
+                    author
+                ));
+            }
+            SignatureStatus::Verified { author } => {
+                ui.label(
+                    egui::RichText::new(format!("Signature: verified, signed by {}", author))
+                        .color(egui::Color32::GREEN),
+                )
+                .on_hover_text(SIGNATURE_COVERAGE);
+            }
+            SignatureStatus::Unknown { signed_by: None } => {
+                ui.label("Signature: unknown, the mod isn't signed");
+            }
+            SignatureStatus::Unknown {
+                signed_by: Some(author),
+            } => {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "Signature: unknown, signed as {} with a key you don't trust",
+                        author
+                    ));
+                    if let SignatureCheck::Signed { public_key, .. } = signature {
+                        if ui.button("Trust").on_hover_text(public_key).clicked() {
+                            match keyring.trust(&author, public_key) {
+                                Ok(()) => keyring.save(keyring_path),
+                                Err(err) => eprintln!("{}", err),
+                            }
+                        }
+                    }
+                });
+            }
+            SignatureStatus::Invalid { reason } => {
+                ui.label(
+                    egui::RichText::new(format!("Signature: invalid, {}", reason))
+                        .color(egui::Color32::RED),
+                );
             }
         }
-
-        println!(
-            "Successfully extracted {:?} to {:?}",
-            file_path.file_name().unwrap(),
-            output_dir
-        );
-
-        Ok(())
+    }
+    // Marks a mod in the list whose files don't match the hashes in its manifest
+    fn show_integrity_problems(ui: &mut egui::Ui, problems: &[IntegrityProblem]) {
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            ui.label(egui::RichText::new("✖").color(egui::Color32::RED))
+                .on_hover_text(problems.join("\n"));
+        }
+    }
+    // Marks a mod in the list whose manifest has problems that didn't stop it from loading
+    fn show_warnings(ui: &mut egui::Ui, warnings: &[ManifestError]) {
+        if !warnings.is_empty() {
+            let warnings: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+            ui.label(egui::RichText::new("⚠").color(egui::Color32::YELLOW))
+                .on_hover_text(warnings.join("\n"));
+        }
+    }
+    fn show_warning_details(ui: &mut egui::Ui, warnings: &[ManifestError]) {
+        for warning in warnings {
+            ui.label(
+                egui::RichText::new(format!("Warning: {}", warning)).color(egui::Color32::YELLOW),
+            );
+        }
+    }
+    // Whether a dropped path is something init_mods knows how to turn into a mod
+    fn is_mod_file(path: &Path) -> bool {
+        path.is_dir() || path.to_str().unwrap_or("").ends_with(".pak") || archive::is_archive(path)
     }
 }
 impl eframe::App for ModManager {
@@ -270,17 +1508,39 @@ impl eframe::App for ModManager {
                             .expect("Failed to open Github");
                     }
                     if ui.button("Launch Game").clicked() {
-                        self.launch();
+                        self.check_drift();
+                        if self.drift.is_empty() {
+                            self.launch();
+                        } else {
+                            self.launch_pending = true;
+                        }
+                    }
+                    if ui.button("Settings").clicked() {
+                        self.show_settings = !self.show_settings;
                     }
                 });
             });
         });
+        self.poll_extractions();
+        self.poll_verifications();
+        self.forget_textures_of_other_mods();
+        self.dependency_problems = check_dependencies(&self.modifications);
+        self.incompatibilities = check_compatibility(&self.modifications, self.game_build);
+        self.show_settings_window(ctx);
+        self.show_fomod_wizard(ctx);
+        self.show_password_prompt(ctx);
+        self.show_unsafe_content_warning(ctx);
+        self.show_drift_window(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
             if !ctx.input(|i| i.raw.dropped_files.is_empty()) {
                 // Get the files that were dropped into the window
                 let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
                 for dropped_file in dropped_files {
                     if let Some(path) = &dropped_file.path {
+                        if !Self::is_mod_file(path) {
+                            eprintln!("{:?} is not a mod file type.", path);
+                            continue;
+                        }
                         // Move the file to the target directory
                         if let Err(err) = self.add_mod(path) {
                             // Handle errors if the move fails
@@ -303,6 +1563,7 @@ impl eframe::App for ModManager {
                     egui::TextEdit::singleline(&mut game_directory_str).clip_text(false);
                 if ui.add(text_edit).changed() {
                     self.game_directory = std::path::PathBuf::from(game_directory_str);
+                    self.refresh_game_build();
                 }
                 if self.validate_game_directory() {
                     ui.label(
@@ -313,6 +1574,9 @@ impl eframe::App for ModManager {
                         egui::RichText::new("Invalid Game Directory").color(egui::Color32::RED),
                     )
                 };
+                if let Some(build) = self.game_build {
+                    ui.label(format!("Build {}", build));
+                }
             });
             ui.separator();
             ui.label(format!(
@@ -333,13 +1597,13 @@ impl eframe::App for ModManager {
                 if ui.button("Refresh Mods").clicked() {
                     self.init_mods();
                 }
+                if ui.button("Verify Mods").clicked() {
+                    self.verify_mods();
+                }
                 if ui.button("Load Mods").clicked() {
-                    if self.validate_game_directory() {
-                        self.load_mods();
-                        self.mod_load_status = "success".to_string();
-                    } else {
-                        self.mod_load_status = "failed".to_string();
-                    }
+                    self.deployment_report = None;
+                    let loaded = self.load_mods(self.ignore_dependency_problems);
+                    self.set_load_status(loaded);
                 }
                 if self.mod_load_status == "success" {
                     ui.label(
@@ -351,9 +1615,96 @@ impl eframe::App for ModManager {
                         egui::RichText::new("Failed to load mods. Check the Game Directory.")
                             .color(egui::Color32::RED),
                     );
+                } else if self.mod_load_status == "unresolved" {
+                    ui.label(
+                        egui::RichText::new("Mods not loaded, resolve the problems below first.")
+                            .color(egui::Color32::RED),
+                    );
+                } else if self.mod_load_status == "verifying" {
+                    ui.label(
+                        egui::RichText::new(
+                            "Mods not loaded, try again once their files have been checked.",
+                        )
+                        .color(egui::Color32::YELLOW),
+                    );
+                } else if self.mod_load_status == "restore_failed" {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "Some files couldn't be restored: {}",
+                            self.restore_errors.join(", ")
+                        ))
+                        .color(egui::Color32::RED),
+                    );
+                } else if self.mod_load_status == "drift" {
+                    ui.label(
+                        egui::RichText::new("~mods changed since mods were loaded, re-sync them.")
+                            .color(egui::Color32::YELLOW),
+                    );
+                } else if self.mod_load_status == "load_error" {
+                    if let Some(err) = &self.load_error {
+                        ui.label(
+                            egui::RichText::new(format!("Mods not loaded. {}", err))
+                                .color(egui::Color32::RED),
+                        );
+                    }
                 }
             });
+            if let Some(report) = &self.deployment_report {
+                let color = if report.failed.is_empty() {
+                    egui::Color32::GREEN
+                } else {
+                    egui::Color32::RED
+                };
+                ui.label(egui::RichText::new(report.summary()).color(color));
+            }
+            if self.mod_load_status == "success" {
+                for file in self.deployment.renamed() {
+                    ui.label(format!(
+                        "{} from {} was deployed as {}, another mod has a pak with the same name",
+                        file.file_name, file.mod_name, file.deployed_name
+                    ));
+                }
+            }
+            for note in &self.resolution_notes {
+                ui.label(note);
+            }
+            if self.has_unresolved_problems() {
+                for problem in &self.dependency_problems {
+                    ui.label(egui::RichText::new(problem.to_string()).color(egui::Color32::YELLOW));
+                }
+                for (index, problem) in &self.incompatibilities {
+                    if self.modifications[*index].is_enabled() {
+                        ui.label(
+                            egui::RichText::new(problem.to_string()).color(egui::Color32::YELLOW),
+                        );
+                    }
+                }
+                for modification in self.modifications.iter() {
+                    if modification.is_enabled() {
+                        for problem in modification.integrity_problems() {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{}: {}",
+                                    modification.name(),
+                                    problem
+                                ))
+                                .color(egui::Color32::YELLOW),
+                            );
+                        }
+                    }
+                }
+                ui.checkbox(&mut self.ignore_dependency_problems, "Load mods anyway");
+            }
+            for err in &self.disk_space_errors {
+                ui.label(egui::RichText::new(err.to_string()).color(egui::Color32::RED));
+            }
+            for err in &self.install_errors {
+                ui.label(egui::RichText::new(err).color(egui::Color32::RED));
+            }
+            self.show_extraction_progress(ui);
+            self.show_verification_progress(ui);
             ui.separator();
+            let locale = self.settings.locale();
             ui.columns(2, |columns| {
                 egui::ScrollArea::vertical().max_height(columns[0].available_height()).show(&mut columns[0], |ui| {
                     ui.horizontal(|ui| {
@@ -372,6 +1723,8 @@ impl eframe::App for ModManager {
                                     }
                                 }
                             }
+                            // Drops whatever can't be enabled alongside the rest
+                            self.resolve_enabled(None);
                         }
                         if ui.button("Disable All").clicked() {
                             for modification in self.modifications.iter_mut() {
@@ -387,14 +1740,17 @@ impl eframe::App for ModManager {
                                     }
                                 }
                             }
+                            self.resolve_enabled(None);
                         }
                     });
+                    let mut toggled = None;
                     for (index, modification) in self.modifications.iter_mut().enumerate() {
                         ui.horizontal(|ui| match modification {
                             LoosePak(lp) => {
                                 let mut enabled = lp.enabled;
                                 if ui.checkbox(&mut enabled, "").changed() {
                                     lp.enabled = enabled;
+                                    toggled = Some(index);
                                 }
                                 if ui
                                     .selectable_label(
@@ -411,48 +1767,128 @@ impl eframe::App for ModManager {
                                 let mut enabled = cm.enabled;
                                 if ui.checkbox(&mut enabled, "").changed() {
                                     cm.enabled = enabled;
+                                    toggled = Some(index);
                                 }
                                 if ui
                                     .selectable_label(
                                         self.selected_mod_index == Some(index),
-                                        format!("{}", cm.name),
+                                        cm.localized_name(&locale),
                                     )
                                     .clicked()
                                 {
                                     self.selected_mod_index = Some(index);
                                 }
+                                Self::show_incompatibility(ui, &self.incompatibilities, index);
+                                Self::show_integrity_problems(ui, &cm.integrity_problems);
+                                Self::show_warnings(ui, &cm.warnings);
+                                Self::show_signature_status(
+                                    ui,
+                                    &self.keyring.status(&cm.signature, &cm.integrity_problems),
+                                    self.verifier.is_pending(&cm.file_path),
+                                );
                             }
                             MultiPak(mp) => {
                                 let mut enabled = mp.enabled;
                                 if ui.checkbox(&mut enabled, "").changed() {
                                     mp.enabled = enabled;
+                                    toggled = Some(index);
                                 }
                                 if ui
                                     .selectable_label(
                                         self.selected_mod_index == Some(index),
-                                        format!("{}", mp.name),
+                                        mp.localized_name(&locale),
                                     )
                                     .clicked()
                                 {
                                     self.selected_mod_index = Some(index);
                                 }
+                                Self::show_incompatibility(ui, &self.incompatibilities, index);
+                                Self::show_integrity_problems(ui, &mp.integrity_problems);
+                                Self::show_warnings(ui, &mp.warnings);
+                                Self::show_signature_status(
+                                    ui,
+                                    &self.keyring.status(&mp.signature, &mp.integrity_problems),
+                                    self.verifier.is_pending(&mp.path),
+                                );
                             }
                         });
                     }
+                    if toggled.is_some() {
+                        self.resolve_enabled(toggled);
+                    }
                 });
 
                 columns[1].heading("Mod Details:");
-                if let Some(selected_index) = self.selected_mod_index {
-                    let selected_mod = self.modifications.get_mut(selected_index).unwrap();
+                let selected_mod = self
+                    .selected_mod_index
+                    .and_then(|selected_index| self.modifications.get_mut(selected_index));
+                if let Some(selected_mod) = selected_mod {
                     match selected_mod {
                         LoosePak(lp) => {
                             columns[1].label(format!("Name: {}", lp.name));
                         }
                         Complete(cm) => {
-                            columns[1].label(format!("Name: {}", cm.name));
+                            columns[1].label(format!("Name: {}", cm.localized_name(&locale)));
                             columns[1].label(format!("Version: {}", cm.version));
                             columns[1].label(format!("Author: {}", cm.author));
-                            columns[1].label(format!("Description: {}", cm.description));
+                            columns[1].label(format!(
+                                "Description: {}",
+                                cm.localized_description(&locale)
+                            ));
+                            Self::show_metadata(
+                                ctx,
+                                &mut columns[1],
+                                &mut self.textures,
+                                &cm.metadata,
+                                &cm.source,
+                            );
+                            Self::show_signature(
+                                &mut columns[1],
+                                &mut self.keyring,
+                                &self.keyring_path,
+                                &cm.signature,
+                                &cm.integrity_problems,
+                                self.verifier.is_pending(&cm.file_path),
+                            );
+                            Self::show_warning_details(&mut columns[1], &cm.warnings);
+                            if !cm.options.is_empty() {
+                                columns[1].separator();
+                                columns[1].heading("Options:");
+                                let mut changes = Vec::new();
+                                for (group_index, group) in cm.options.groups().iter().enumerate() {
+                                    columns[1].label(egui::RichText::new(&group.name).strong());
+                                    for (option_index, option) in group.options.iter().enumerate() {
+                                        let available =
+                                            cm.options.is_available(group_index, option_index);
+                                        let mut selected = option.selected;
+                                        let response = columns[1]
+                                            .add_enabled_ui(available, |ui| match group.kind {
+                                                GroupKind::ExactlyOne | GroupKind::AtMostOne => {
+                                                    ui.radio(selected, &option.name)
+                                                }
+                                                GroupKind::Any => {
+                                                    ui.checkbox(&mut selected, &option.name)
+                                                }
+                                            })
+                                            .inner;
+                                        if response.clicked() {
+                                            let selected = match group.kind {
+                                                GroupKind::ExactlyOne => true,
+                                                // Clicking the picked option again clears the group
+                                                GroupKind::AtMostOne => !option.selected,
+                                                GroupKind::Any => selected,
+                                            };
+                                            changes.push((group_index, option_index, selected));
+                                        }
+                                        if !option.description.is_empty() {
+                                            response.on_hover_text(&option.description);
+                                        }
+                                    }
+                                }
+                                for (group, option, selected) in changes {
+                                    cm.options.set_selected(group, option, selected);
+                                }
+                            }
                             if !cm.images.is_empty() {
                                 columns[1].separator();
                                 columns[1].heading("Images:");
@@ -476,7 +1912,9 @@ impl eframe::App for ModManager {
                                     // Dynamically load the image as a texture
                                     if let Some(texture) = Self::load_image_to_texture(
                                         ctx,
-                                        &cm.file_path.join(image_path),
+                                        &mut self.textures,
+                                        &cm.source,
+                                        image_path,
                                     ) {
                                         columns[1].add(
                                             egui::Image::new(&texture)
@@ -490,33 +1928,112 @@ impl eframe::App for ModManager {
                             }
                         }
                         MultiPak(mp) => {
-                            columns[1].label(format!("Name: {}", mp.name));
+                            columns[1].label(format!("Name: {}", mp.localized_name(&locale)));
                             columns[1].label(format!("Version: {}", mp.version));
                             columns[1].label(format!("Author: {}", mp.author));
-                            columns[1].label(format!("Description: {}", mp.description));
+                            columns[1].label(format!(
+                                "Description: {}",
+                                mp.localized_description(&locale)
+                            ));
+                            Self::show_metadata(
+                                ctx,
+                                &mut columns[1],
+                                &mut self.textures,
+                                &mp.metadata,
+                                &mp.source,
+                            );
+                            Self::show_signature(
+                                &mut columns[1],
+                                &mut self.keyring,
+                                &self.keyring_path,
+                                &mp.signature,
+                                &mp.integrity_problems,
+                                self.verifier.is_pending(&mp.path),
+                            );
+                            Self::show_warning_details(&mut columns[1], &mp.warnings);
                             columns[1].separator();
-                            columns[1].horizontal(|mut ui| {
-                                ui.heading("Select which pak to load:");
-                                egui::ComboBox::from_label("")
-                                    .selected_text(format!("{}", mp.selected_pak().name))
-                                    .show_ui(&mut ui, |ui| {
-                                        for (index, pak) in mp.paks.iter().enumerate() {
-                                            if ui
-                                                .selectable_label(
-                                                    mp.selected_pak().name == pak.name,
-                                                    format!("{}", pak.name),
-                                                )
-                                                .clicked()
-                                            {
-                                                mp.selected_pak = index;
-                                                println!("{:?}", mp.selected_pak());
-                                            }
+                            if mp.multi_select {
+                                columns[1].heading("Select which paks to load:");
+                                for (index, pak) in mp.paks.iter_mut().enumerate() {
+                                    columns[1].horizontal(|ui| {
+                                        ui.checkbox(&mut pak.enabled, "");
+                                        // Clicking the name shows the pak's details below
+                                        if ui
+                                            .selectable_label(
+                                                mp.selected_pak == index,
+                                                pak.localized_name(&locale),
+                                            )
+                                            .clicked()
+                                        {
+                                            mp.selected_pak = index;
+                                            self.current_image = 0;
                                         }
                                     });
-                            });
-                            columns[1].label(format!("Name: {}", mp.selected_pak().name));
-                            columns[1]
-                                .label(format!("Description: {}", mp.selected_pak().description));
+                                }
+                            } else if !mp.axes.is_empty() {
+                                columns[1].heading("Select which variant to load:");
+                                let mut changed = false;
+                                for (axis_index, axis) in mp.axes.iter().enumerate() {
+                                    let selected = &mut mp.axis_selection[axis_index];
+                                    columns[1].horizontal(|ui| {
+                                        ui.label(format!("{}:", axis.name));
+                                        egui::ComboBox::from_id_salt(("variant_axis", axis_index))
+                                            .selected_text(&axis.values[*selected])
+                                            .show_ui(ui, |ui| {
+                                                for (value_index, value) in
+                                                    axis.values.iter().enumerate()
+                                                {
+                                                    changed |= ui
+                                                        .selectable_value(selected, value_index, value)
+                                                        .changed();
+                                                }
+                                            });
+                                    });
+                                }
+                                match mp.variant_index() {
+                                    Some(index) if changed => {
+                                        mp.selected_pak = index;
+                                        self.current_image = 0;
+                                    }
+                                    Some(_) => {}
+                                    None => {
+                                        columns[1].label(
+                                            egui::RichText::new(
+                                                "This mod has no pak for that combination.",
+                                            )
+                                            .color(egui::Color32::RED),
+                                        );
+                                    }
+                                }
+                            } else {
+                                columns[1].horizontal(|mut ui| {
+                                    ui.heading("Select which pak to load:");
+                                    egui::ComboBox::from_label("")
+                                        .selected_text(mp.selected_pak().localized_name(&locale))
+                                        .show_ui(&mut ui, |ui| {
+                                            for (index, pak) in mp.paks.iter().enumerate() {
+                                                if ui
+                                                    .selectable_label(
+                                                        mp.selected_pak().name == pak.name,
+                                                        pak.localized_name(&locale),
+                                                    )
+                                                    .clicked()
+                                                {
+                                                    mp.selected_pak = index;
+                                                    println!("{:?}", mp.selected_pak());
+                                                }
+                                            }
+                                        });
+                                });
+                            }
+                            columns[1].label(format!(
+                                "Name: {}",
+                                mp.selected_pak().localized_name(&locale)
+                            ));
+                            columns[1].label(format!(
+                                "Description: {}",
+                                mp.selected_pak().localized_description(&locale)
+                            ));
                             if !mp.selected_pak().images.is_empty() {
                                 columns[1].separator();
                                 columns[1].heading("Images:");
@@ -542,7 +2059,9 @@ impl eframe::App for ModManager {
                                     // Dynamically load the image as a texture
                                     if let Some(texture) = Self::load_image_to_texture(
                                         ctx,
-                                        &mp.path.join("images").join(image_path),
+                                        &mut self.textures,
+                                        &mp.source,
+                                        image_path,
                                     ) {
                                         columns[1].add(
                                             egui::Image::new(&texture)
@@ -1,4 +1,8 @@
+mod keyring;
 mod manager;
 mod mod_builder;
+mod settings;
+pub use keyring::*;
 pub use manager::*;
 pub use mod_builder::*;
+pub use settings::*;
@@ -1,8 +1,12 @@
-use crate::mod_types::{ModType, Modification, MultiPak, Pak};
+use crate::mod_types::manifest::ManifestFormat;
+use crate::mod_types::{
+    author_key_path, hash_mod_files, is_locale, manifest, schema, AuthorKey, Contributor, Metadata,
+    ModSource, ModType, Modification, MultiPak, Pak, Redistribution, Translations, AUTHOR_KEY_FILE,
+    SIGNATURE_COVERAGE, SIGNATURE_FILE,
+};
 use egui::text_edit;
-use std::fs::{rename, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::fs::rename;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 
 #[derive(Debug)]
@@ -13,6 +17,19 @@ pub struct ModBuilder {
     img_dir: PathBuf,
     pak_dir: PathBuf,
     modification: ModType,
+    // Which of mod.json, mod.toml or mod.yaml the mod is built with
+    manifest_format: ManifestFormat,
+    // The tags as typed, split into the manifest's list as they're edited
+    tags_text: String,
+    // Languages being translated into, shared by the mod and its paks
+    locales: Vec<String>,
+    new_locale: String,
+    // Why the last build was refused, shown until the next one succeeds
+    build_error: String,
+    // Loaded from author_key.json, None until a key is generated
+    author_key: Option<AuthorKey>,
+    key_author: String,
+    sign_builds: bool,
 }
 
 fn toggle(ui: &mut egui::Ui, on: &mut bool) -> egui::Response {
@@ -41,6 +58,192 @@ fn toggle(ui: &mut egui::Ui, on: &mut bool) -> egui::Response {
 
     response
 }
+// The optional details shared by both kinds of mod. `images` are the ones the cover can be
+// picked from, relative to the root of the mod.
+fn edit_metadata(
+    ui: &mut egui::Ui,
+    metadata: &mut Metadata,
+    tags_text: &mut String,
+    images: &[PathBuf],
+) {
+    ui.horizontal(|ui| {
+        ui.label("Tags: ");
+        if ui
+            .add(text_edit::TextEdit::singleline(tags_text).hint_text("comma separated"))
+            .changed()
+        {
+            metadata.tags = tags_text
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Homepage: ");
+        ui.add(text_edit::TextEdit::singleline(&mut metadata.homepage));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Source: ");
+        ui.add(text_edit::TextEdit::singleline(&mut metadata.repository));
+    });
+    ui.horizontal(|ui| {
+        ui.label("License: ");
+        ui.add(text_edit::TextEdit::singleline(&mut metadata.license).hint_text("CC-BY-4.0"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Redistribution: ");
+        egui::ComboBox::from_id_salt("redistribution")
+            .selected_text(metadata.redistribution.label())
+            .show_ui(ui, |ui| {
+                for redistribution in Redistribution::ALL {
+                    ui.selectable_value(
+                        &mut metadata.redistribution,
+                        redistribution,
+                        redistribution.label(),
+                    );
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        ui.label("Last updated: ");
+        ui.add(text_edit::TextEdit::singleline(&mut metadata.updated).hint_text("YYYY-MM-DD"));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Cover image: ");
+        let selected = match &metadata.cover {
+            Some(cover) => cover.to_string_lossy().into_owned(),
+            None => "None".to_string(),
+        };
+        egui::ComboBox::from_id_salt("cover")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut metadata.cover, None, "None");
+                for image in images {
+                    ui.selectable_value(
+                        &mut metadata.cover,
+                        Some(image.clone()),
+                        image.to_string_lossy(),
+                    );
+                }
+            });
+    });
+    ui.label("Contributors: ");
+    let mut removed = None;
+    for (index, contributor) in metadata.contributors.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(text_edit::TextEdit::singleline(&mut contributor.name).hint_text("Name"));
+            ui.add(text_edit::TextEdit::singleline(&mut contributor.role).hint_text("Role"));
+            if ui.button("Remove").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        metadata.contributors.remove(index);
+    }
+    if ui.button("Add contributor").clicked() {
+        metadata.contributors.push(Contributor::default());
+    }
+}
+// Lets languages be added and removed, returning the one removed if any
+fn edit_locales(
+    ui: &mut egui::Ui,
+    locales: &mut Vec<String>,
+    new_locale: &mut String,
+) -> Option<String> {
+    let mut removed = None;
+    ui.horizontal(|ui| {
+        ui.label("Languages: ");
+        for (index, locale) in locales.iter().enumerate() {
+            if ui.button(format!("{} ✕", locale)).clicked() {
+                removed = Some(index);
+            }
+        }
+        ui.add(
+            text_edit::TextEdit::singleline(new_locale)
+                .hint_text("fr, pt-BR")
+                .desired_width(60.0),
+        );
+        let valid = is_locale(new_locale.trim())
+            && !locales.iter().any(|locale| locale == new_locale.trim());
+        if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+            locales.push(new_locale.trim().to_string());
+            new_locale.clear();
+        }
+    });
+    removed.map(|index| locales.remove(index))
+}
+// Shows the original name and description next to their translation in each language
+fn edit_translations(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    name: &str,
+    description: &str,
+    translations: &mut Translations,
+    locales: &[String],
+) {
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label("Name");
+        ui.label("Description");
+        ui.end_row();
+        ui.label("Original");
+        ui.label(name);
+        ui.label(description);
+        ui.end_row();
+        for locale in locales {
+            ui.label(locale);
+            let mut translation = translations.0.get(locale).cloned().unwrap_or_default();
+            let mut changed = ui
+                .add(text_edit::TextEdit::singleline(&mut translation.name).hint_text(name))
+                .changed();
+            changed |= ui
+                .add(
+                    text_edit::TextEdit::multiline(&mut translation.description)
+                        .hint_text(description),
+                )
+                .changed();
+            if changed {
+                if translation.is_empty() {
+                    translations.0.remove(locale);
+                } else {
+                    translations.0.insert(locale.clone(), translation);
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+// Writes the manifest in the chosen format, dropping any left over from an earlier attempt in
+// another one, which would be found instead
+fn write_manifest(mod_dir: &Path, format: ManifestFormat, contents: &str) {
+    for other in ManifestFormat::ALL {
+        let _ = fs::remove_file(mod_dir.join(other.file_name()));
+    }
+    fs::write(mod_dir.join(format.file_name()), contents).unwrap();
+}
+// Ships the schema with the built mod and checks the manifest the same way the manager will
+fn check_build(mod_dir: &Path, multi_pak: bool) -> Result<(), String> {
+    schema::write_schema(mod_dir, multi_pak)?;
+    let source = ModSource::Directory(mod_dir.to_path_buf());
+    let manifest = manifest::read_manifest(&source)?;
+    let checked = if multi_pak {
+        schema::validate_multi_pak(&source, &manifest)
+    } else {
+        schema::validate_modification(&source, &manifest)
+    };
+    // The manager would still load a mod with warnings, but a fresh build shouldn't have any
+    match checked {
+        Ok(warnings) if warnings.is_empty() => Ok(()),
+        Ok(warnings) => Err(format!(
+            "The mod can't be built yet:{}",
+            schema::ManifestErrors(warnings)
+        )),
+        Err(errors) => Err(format!("The mod can't be built yet:{}", errors)),
+    }
+}
 impl ModBuilder {
     pub fn new() -> Self {
         Self {
@@ -50,6 +253,92 @@ impl ModBuilder {
             img_dir: env::current_dir().unwrap().join("temp").join("images"),
             pak_dir: env::current_dir().unwrap().join("temp").join("paks"),
             modification: ModType::Complete(Modification::new()),
+            manifest_format: ManifestFormat::default(),
+            tags_text: String::new(),
+            locales: Vec::new(),
+            new_locale: String::new(),
+            build_error: String::new(),
+            author_key: None,
+            key_author: String::new(),
+            sign_builds: false,
+        }
+        .with_author_key()
+    }
+    fn with_author_key(mut self) -> Self {
+        let Some(path) = author_key_path() else {
+            return self;
+        };
+        // Keys used to be kept in the working directory, right next to the mods being built
+        let old_path = self.working_dir.join(AUTHOR_KEY_FILE);
+        if !path.exists() && old_path.exists() {
+            match AuthorKey::load(&old_path).and_then(|key| key.save(&path)) {
+                Ok(()) => {
+                    if let Err(err) = fs::remove_file(&old_path) {
+                        eprintln!("Failed to remove {:?}: {}", old_path, err);
+                    }
+                }
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        if path.exists() {
+            match AuthorKey::load(&path) {
+                Ok(key) => {
+                    self.author_key = Some(key);
+                    self.sign_builds = true;
+                }
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        self
+    }
+    // Signs the built mod if the author asked for it, dropping any signature left from before
+    fn sign_build(&self) -> Result<(), String> {
+        let _ = fs::remove_file(self.temp_dir.join(SIGNATURE_FILE));
+        match &self.author_key {
+            Some(key) if self.sign_builds => key.sign(&self.temp_dir),
+            _ => Ok(()),
+        }
+    }
+    fn edit_signing(&mut self, ui: &mut egui::Ui) {
+        match &self.author_key {
+            Some(key) => {
+                ui.checkbox(&mut self.sign_builds, format!("Sign as {}", key.author))
+                    .on_hover_text(SIGNATURE_COVERAGE);
+                if ui
+                    .button("Copy public key")
+                    .on_hover_text("Players add this to their trusted authors")
+                    .clicked()
+                {
+                    ui.ctx().copy_text(key.public_key());
+                }
+            }
+            None => {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.key_author)
+                        .hint_text("Author name")
+                        .desired_width(100.0),
+                );
+                let button = ui.add_enabled(
+                    !self.key_author.trim().is_empty(),
+                    egui::Button::new("Generate signing key"),
+                );
+                if button.clicked() {
+                    let key = author_key_path()
+                        .ok_or_else(|| "Couldn't find a folder to keep the key in".to_string())
+                        .and_then(|path| {
+                            let key = AuthorKey::generate(self.key_author.trim())?;
+                            key.save(&path)?;
+                            Ok(key)
+                        });
+                    match key {
+                        Ok(key) => {
+                            self.author_key = Some(key);
+                            self.sign_builds = true;
+                        }
+                        Err(err) => self.build_error = err,
+                    }
+                }
+            }
         }
     }
     pub fn check_dirs_multipak(&mut self) {
@@ -89,14 +378,33 @@ impl eframe::App for ModBuilder {
                         }
                         _ => {}
                     }
+                    // Hash the paks and images so the manager can tell if they get corrupted
+                    let hashes = match hash_mod_files(&ModSource::Directory(self.temp_dir.clone()))
+                    {
+                        Ok(hashes) => hashes,
+                        Err(err) => {
+                            self.build_error = err;
+                            return;
+                        }
+                    };
+                    match &mut self.modification {
+                        ModType::Complete(modification) => modification.hashes = hashes,
+                        ModType::MultiPak(modification) => modification.hashes = hashes,
+                        _ => {}
+                    }
                     match &self.modification {
                         ModType::Complete(modification) => {
-                            match serde_json::to_string_pretty(&modification) {
+                            match manifest::serialize(&modification, self.manifest_format) {
                                 Ok(json) => {
                                     if !modification.name.is_empty() {
-                                        let mut file = File::create("temp/mod.json").unwrap();
-                                        file.write_all(json.as_bytes()).unwrap();
-                                        drop(file);
+                                        write_manifest(&self.temp_dir, self.manifest_format, &json);
+                                        if let Err(err) = check_build(&self.temp_dir, false)
+                                            .and_then(|_| self.sign_build())
+                                        {
+                                            self.build_error = err;
+                                            return;
+                                        }
+                                        self.build_error.clear();
                                         rename(
                                             &self.temp_dir.as_path(),
                                             &self.working_dir.join(&modification.name),
@@ -108,12 +416,17 @@ impl eframe::App for ModBuilder {
                             }
                         }
                         ModType::MultiPak(modification) => {
-                            match serde_json::to_string_pretty(&modification) {
+                            match manifest::serialize(&modification, self.manifest_format) {
                                 Ok(json) => {
                                     if !modification.name.is_empty() {
-                                        let mut file = File::create("temp/mod.json").unwrap();
-                                        file.write_all(json.as_bytes()).unwrap();
-                                        drop(file);
+                                        write_manifest(&self.temp_dir, self.manifest_format, &json);
+                                        if let Err(err) = check_build(&self.temp_dir, true)
+                                            .and_then(|_| self.sign_build())
+                                        {
+                                            self.build_error = err;
+                                            return;
+                                        }
+                                        self.build_error.clear();
                                         rename(
                                             &self.temp_dir.as_path(),
                                             &self.working_dir.join(&modification.name),
@@ -127,6 +440,17 @@ impl eframe::App for ModBuilder {
                         _ => {}
                     }
                 }
+                egui::ComboBox::from_label("Manifest format")
+                    .selected_text(self.manifest_format.label())
+                    .show_ui(ui, |ui| {
+                        for format in ManifestFormat::ALL {
+                            ui.selectable_value(&mut self.manifest_format, format, format.label());
+                        }
+                    });
+                self.edit_signing(ui);
+                if !self.build_error.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.build_error);
+                }
             })
         });
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -201,12 +525,16 @@ impl eframe::App for ModBuilder {
                     if self.building_multi_pak {
                         println!("Switch to MultiPak");
                         self.modification = ModType::MultiPak(MultiPak::default());
+                        self.tags_text.clear();
+                        self.locales.clear();
                         self.check_dirs_complete();
                         fs::remove_dir_all(&self.working_dir.join("temp"))
                             .expect("failed to find temp dir");
                     } else {
                         println!("Switch to Single Pak");
                         self.modification = ModType::Complete(Modification::new());
+                        self.tags_text.clear();
+                        self.locales.clear();
                         self.check_dirs_complete();
                         fs::remove_dir_all(&self.working_dir.join("temp"))
                             .expect("failed to find temp dir");
@@ -256,6 +584,22 @@ impl eframe::App for ModBuilder {
                             println!("{}", cm.version);
                         }
                     });
+                    edit_metadata(ui, &mut cm.metadata, &mut self.tags_text, &cm.images);
+                    ui.heading("Translations:");
+                    if let Some(locale) = edit_locales(ui, &mut self.locales, &mut self.new_locale)
+                    {
+                        cm.translations.0.remove(&locale);
+                    }
+                    if !self.locales.is_empty() {
+                        edit_translations(
+                            ui,
+                            "translations",
+                            &cm.name,
+                            &cm.description,
+                            &mut cm.translations,
+                            &self.locales,
+                        );
+                    }
                 }
                 ModType::MultiPak(mp) => {
                     ui.label("Drag and drop .pak files on this window to add them to the mod.");
@@ -295,6 +639,37 @@ impl eframe::App for ModBuilder {
                             println!("{}", mp.version);
                         }
                     });
+                    let images: Vec<PathBuf> = self
+                        .img_dir
+                        .read_dir()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|image| image.ok())
+                        .map(|image| Path::new("images").join(image.file_name()))
+                        .collect();
+                    edit_metadata(ui, &mut mp.metadata, &mut self.tags_text, &images);
+                    ui.heading("Translations:");
+                    if let Some(locale) = edit_locales(ui, &mut self.locales, &mut self.new_locale)
+                    {
+                        mp.translations.0.remove(&locale);
+                        for pak in &mut mp.paks {
+                            pak.translations.0.remove(&locale);
+                        }
+                    }
+                    if !self.locales.is_empty() {
+                        edit_translations(
+                            ui,
+                            "translations",
+                            &mp.name,
+                            &mp.description,
+                            &mut mp.translations,
+                            &self.locales,
+                        );
+                    }
+                    ui.checkbox(
+                        &mut mp.multi_select,
+                        "Let users enable several paks at once",
+                    );
                     ui.heading("Paks:");
                     ui.separator();
 
@@ -319,10 +694,20 @@ impl eframe::App for ModBuilder {
                                     println!("{}", pak.description);
                                 }
                             });
+                            if !self.locales.is_empty() {
+                                edit_translations(
+                                    ui,
+                                    ("pak_translations", &pak.pak),
+                                    &pak.name,
+                                    &pak.description,
+                                    &mut pak.translations,
+                                    &self.locales,
+                                );
+                            }
                             ui.label("Choose images to be associated with .pak: ");
                             for image in self.img_dir.read_dir().unwrap() {
-                                let image =
-                                    PathBuf::from(image.unwrap().file_name().to_str().unwrap());
+                                // Pak images are listed relative to the root of the mod
+                                let image = Path::new("images").join(image.unwrap().file_name());
                                 ui.horizontal(|ui| {
                                     ui.label(image.to_str().unwrap());
                                     let mut is_selected = pak.images.contains(&image);
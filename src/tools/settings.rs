@@ -0,0 +1,46 @@
+use crate::archive::UnsafeFilePolicy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Manager preferences, saved as settings.json next to the executable
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub read_archives_in_place: bool,
+    pub unsafe_file_policy: UnsafeFilePolicy,
+    // Save mod.json files from older formats back in the current one, keeping a backup
+    pub rewrite_upgraded_manifests: bool,
+    // Locale to show mod names and descriptions in, like "fr" or "pt-BR". Empty follows the system.
+    pub language: String,
+    // Check every mod's files against the hashes in its manifest whenever mods are scanned
+    pub verify_on_scan: bool,
+    // Hash the paks copied into ~mods against the originals, copying them again if they differ
+    pub verify_deployment: bool,
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|err| {
+                eprintln!("Failed to parse settings {:?}: {}", path, err);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+    pub fn locale(&self) -> String {
+        if self.language.is_empty() {
+            sys_locale::get_locale().unwrap_or_default()
+        } else {
+            self.language.clone()
+        }
+    }
+    pub fn save(&self, path: &Path) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => fs::write(path, json)
+                .unwrap_or_else(|err| eprintln!("Failed to save settings {:?}: {}", path, err)),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+}